@@ -75,9 +75,10 @@ if (Get-Command cce -ErrorAction SilentlyContinue) {
         foreach ($line in $expanded) {
             if ([string]::IsNullOrWhiteSpace($line)) { continue }
             $trimmed = $line.Trim()
-            if ($trimmed -match '^export\s+([A-Z0-9_]+)="([^"]*)"$') {
+            if ($trimmed -match "^export\s+([A-Z0-9_]+)='(.*)'$") {
                 $name = $matches[1]
-                $value = $matches[2]
+                # Values are single-quoted for POSIX shells, with ' written as '\''
+                $value = $matches[2].Replace("'\''", "'")
                 [Environment]::SetEnvironmentVariable($name, $value, 'Process')
             } elseif ($trimmed -match '^unset\s+([A-Z0-9_]+)$') {
                 $name = $matches[1]
@@ -92,7 +93,8 @@ if (Get-Command cce -ErrorAction SilentlyContinue) {
         )
 
         $env:CCE_SHELL_INTEGRATION = '1'
-        $output = & $script:CceBinary @Arguments 2>$null
+        # Only the commands to apply are captured; errors and the picker reach the console
+        $output = & $script:CceBinary @Arguments
         $status = $LASTEXITCODE
         Remove-Item Env:CCE_SHELL_INTEGRATION -ErrorAction SilentlyContinue
         return [PSCustomObject]@{
@@ -107,40 +109,68 @@ if (Get-Command cce -ErrorAction SilentlyContinue) {
             [string[]]$Args
         )
 
-        if ($Args.Length -ge 2 -and $Args[0] -eq 'use') {
+        $wantsHelp = ($Args -contains '-h') -or ($Args -contains '--help')
+        if ($Args.Length -ge 1 -and $Args[0] -eq 'use' -and -not $wantsHelp) {
+            # Also covers `cce use` with the picker, `cce use -` and `cce use --group <name>`
             $result = Invoke-CceBinary -Arguments $Args
-            if ($result.Status -eq 0 -and $result.Output) {
-                Apply-CceEnvironment -Lines $result.Output
-                Write-Host "⚡ Switched to service provider '$($Args[1])'"
-                Write-Host '✅ Environment variables are now active in current terminal'
+            # The binary already reported the failure; keep its exit code (see `cce --help`)
+            if ($result.Status -ne 0) {
+                $global:LASTEXITCODE = $result.Status
                 return
             }
-        } elseif ($Args.Length -ge 1 -and $Args[0] -eq 'clear') {
+            Apply-CceEnvironment -Lines $result.Output
+            Write-Host "⚡ Switched to service provider '$env:CCE_PROVIDER'"
+            Write-Host '✅ Environment variables are now active in current terminal'
+            return
+        } elseif ($Args.Length -ge 1 -and $Args[0] -eq 'model' -and -not $wantsHelp) {
             $result = Invoke-CceBinary -Arguments $Args
-            if ($result.Status -eq 0 -and $result.Output) {
-                Apply-CceEnvironment -Lines $result.Output
-                Write-Host '🧹 Cleared service provider configuration'
-                Write-Host '✅ Environment variables are now unset in current terminal'
+            if ($result.Status -ne 0) {
+                $global:LASTEXITCODE = $result.Status
                 return
             }
+            Apply-CceEnvironment -Lines $result.Output
+            Write-Host "🎯 Switched '$env:CCE_PROVIDER' to model '$env:ANTHROPIC_MODEL'"
+            return
+        } elseif ($Args.Length -ge 1 -and $Args[0] -eq 'clear' -and -not $wantsHelp) {
+            $result = Invoke-CceBinary -Arguments $Args
+            if ($result.Status -ne 0) {
+                $global:LASTEXITCODE = $result.Status
+                return
+            }
+            Apply-CceEnvironment -Lines $result.Output
+            Write-Host '🧹 Cleared service provider configuration'
+            Write-Host '✅ Environment variables are now unset in current terminal'
+            return
+        } elseif ($Args.Length -ge 1 -and $Args[0] -eq 'tui') {
+            # The TUI owns the console, so it leaves the commands for this session in a file
+            $handoff = [System.IO.Path]::GetTempFileName()
+            $env:CCE_TUI_HANDOFF = $handoff
+            try {
+                & $script:CceBinary @Args
+            } finally {
+                Remove-Item Env:CCE_TUI_HANDOFF -ErrorAction SilentlyContinue
+            }
+            $lines = Get-Content $handoff
+            Remove-Item $handoff -ErrorAction SilentlyContinue
+            if ($lines) {
+                Apply-CceEnvironment -Lines $lines
+                if ($env:CCE_PROVIDER) {
+                    Write-Host "⚡ Switched to service provider '$env:CCE_PROVIDER'"
+                } else {
+                    Write-Host '🧹 Cleared service provider configuration'
+                }
+            }
+            return
         }
 
         & $script:CceBinary @Args
     }
 
     function Initialize-CceEnvironment {
-        $configPath = Join-Path $env:USERPROFILE '.cce\config.toml'
-        if (Test-Path $configPath) {
-            $match = Select-String -Path $configPath -Pattern '^current_provider\s*=\s*"([^"]+)"' | Select-Object -First 1
-            if ($match) {
-                $provider = $match.Matches[0].Groups[1].Value
-                if ($provider) {
-                    $result = Invoke-CceBinary -Arguments @('use', $provider)
-                    if ($result.Status -eq 0 -and $result.Output) {
-                        Apply-CceEnvironment -Lines $result.Output
-                    }
-                }
-            }
+        # The default provider, or the one picked by the rules in auto mode; nothing is saved
+        $output = & $script:CceBinary auto --print 2>$null
+        if ($LASTEXITCODE -eq 0 -and $output) {
+            Apply-CceEnvironment -Lines $output
         }
     }
 
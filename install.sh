@@ -236,52 +236,11 @@ append_shell_integration() {
     mkdir -p "$(dirname "$profile")"
     touch "$profile"
 
+    # The wrapper comes from the binary, so it stays in step with upgrades
     cat <<'EOF' >>"$profile"
 # >>> CCE Shell Integration >>>
 if command -v cce >/dev/null 2>&1; then
-  _cce_binary="$(command -v cce)"
-
-  cce() {
-    if [[ "$1" == "use" && -n "$2" ]]; then
-      local _output
-      _output=$(CCE_SHELL_INTEGRATION=1 "$_cce_binary" use "$2" 2>/dev/null)
-      if [[ $? -eq 0 && -n "$_output" ]]; then
-        eval "$_output"
-        echo "⚡ Switched to service provider '$2'"
-        echo "✅ Environment variables are now active in current terminal"
-        return 0
-      fi
-    elif [[ "$1" == "clear" ]]; then
-      local _output
-      _output=$(CCE_SHELL_INTEGRATION=1 "$_cce_binary" clear 2>/dev/null)
-      if [[ $? -eq 0 && -n "$_output" ]]; then
-        eval "$_output"
-        echo "🧹 Cleared service provider configuration"
-        echo "✅ Environment variables are now unset in current terminal"
-        return 0
-      fi
-    fi
-
-    "$_cce_binary" "$@"
-  }
-
-  __cce_apply_current_provider() {
-    local _cfg="$HOME/.cce/config.toml"
-    if [[ -f "$_cfg" ]]; then
-      local _provider
-      _provider=$(awk -F'"' '/^current_provider/ {print $2; exit}' "$_cfg")
-      if [[ -n "$_provider" ]]; then
-        local _boot_output
-        _boot_output=$(CCE_SHELL_INTEGRATION=1 "$_cce_binary" use "$_provider" 2>/dev/null)
-        if [[ $? -eq 0 && -n "$_boot_output" ]]; then
-          eval "$_boot_output"
-        fi
-      fi
-    fi
-  }
-
-  __cce_apply_current_provider
-  unset -f __cce_apply_current_provider
+  eval "$(cce shellenv)"
 fi
 # <<< CCE Shell Integration <<<
 EOF
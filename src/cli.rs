@@ -1,12 +1,29 @@
-use clap::{Parser, Subcommand};
+use crate::completions::{deleted_candidates, group_candidates, provider_candidates};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::engine::ArgValueCandidates;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(
     name = "cce",
     about = "Claude Config Environment - A tool for switching Claude environment variables",
-    version = "0.2.7"
+    version = "0.2.7",
+    after_help = "Exit codes: 0 success, 1 other failure, 2 usage error, 3 not found, \
+                  4 invalid config, 5 environment mismatch, 6 I/O error"
 )]
 pub struct Cli {
+    /// Output format for list, check and current
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Show full tokens instead of masking them
+    #[arg(long, global = true)]
+    pub show_secrets: bool,
+
+    /// Turn off colors, as does setting NO_COLOR
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -15,37 +32,171 @@ pub struct Cli {
 pub enum Commands {
     /// List all service providers
     #[command(alias = "ls")]
-    List,
+    List {
+        /// Only show providers carrying this tag (repeatable)
+        #[arg(long)]
+        tag: Vec<String>,
+        /// Only show members of this group
+        #[arg(long, add = ArgValueCandidates::new(group_candidates))]
+        group: Option<String>,
+    },
 
     /// Add a service provider
     Add {
         /// Provider name
         name: String,
-        /// API URL
-        api_url: String,
-        /// API Token
-        token: String,
+        /// API URL (may be left out with --extends or --preset)
+        #[arg(required_unless_present_any = ["extends", "preset"])]
+        api_url: Option<String>,
+        /// API Token (prompted for without echo when omitted)
+        token: Option<String>,
+        /// Read the token from stdin instead of the command line
+        #[arg(long, conflicts_with = "token")]
+        token_stdin: bool,
         /// Model name (optional)
         #[arg(short, long)]
         model: Option<String>,
+        /// Tags for the provider (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+        /// Group the provider belongs to
+        #[arg(long, add = ArgValueCandidates::new(group_candidates))]
+        group: Option<String>,
+        /// Inherit the URL, token and model from this provider when not given
+        #[arg(long, add = ArgValueCandidates::new(provider_candidates))]
+        extends: Option<String>,
+        /// Fill the URL, auth variable and models from a known gateway (see `cce presets`)
+        #[arg(long)]
+        preset: Option<String>,
+        /// Overwrite an existing provider with the same name
+        #[arg(long)]
+        force: bool,
     },
 
     /// Delete the specified service provider
     #[command(alias = "del")]
     Delete {
         /// Name of provider to delete
+        #[arg(add = ArgValueCandidates::new(provider_candidates))]
+        name: String,
+        /// Delete it even though other providers extend it
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Bring back a deleted service provider from the trash
+    Restore {
+        /// Name of the deleted provider; the latest deletion under that name is restored
+        #[arg(add = ArgValueCandidates::new(deleted_candidates))]
         name: String,
     },
 
     /// Use the specified service provider
     Use {
-        /// Name of provider to use
+        /// Name of provider to use, a unique prefix of it, or "-" for the previous one;
+        /// pick interactively when left out
+        #[arg(add = ArgValueCandidates::new(provider_candidates))]
+        name: Option<String>,
+        /// Use the default member of this group instead
+        #[arg(long, conflicts_with = "name", add = ArgValueCandidates::new(group_candidates))]
+        group: Option<String>,
+    },
+
+    /// Print the provider the time-based rules select right now
+    Auto {
+        /// Let new shells load the rule-selected provider instead of the default
+        #[arg(long)]
+        enable: bool,
+        /// Go back to loading the default provider in new shells
+        #[arg(long, conflicts_with = "enable")]
+        disable: bool,
+        /// Print the shell commands loading the provider a new shell starts with, without
+        /// changing the config
+        #[arg(long, conflicts_with_all = ["enable", "disable"])]
+        print: bool,
+    },
+
+    /// List recent provider switches, newest first
+    History {
+        /// Number of switches to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// List the built-in catalog of gateway presets
+    Presets,
+
+    /// Switch the model of the active provider, in this shell only unless --save is given
+    Model {
+        /// Model to use, e.g. claude-opus-4-1
+        model: String,
+        /// Store the model on the provider so new shells pick it up too
+        #[arg(long)]
+        save: bool,
+    },
+
+    /// List the models a provider's gateway offers
+    Models {
+        /// Provider whose gateway to ask
+        #[arg(add = ArgValueCandidates::new(provider_candidates))]
         name: String,
+        /// Only use the cached list from the last successful fetch
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Show a single service provider
+    Show {
+        /// Provider to show
+        #[arg(add = ArgValueCandidates::new(provider_candidates))]
+        name: String,
+        /// Apply inheritance from `extends` and show the effective values
+        #[arg(long)]
+        resolved: bool,
+    },
+
+    /// Add or remove tags on a service provider
+    Tag {
+        /// Provider to tag
+        #[arg(add = ArgValueCandidates::new(provider_candidates))]
+        name: String,
+        /// Tags to add (or remove with --remove)
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// Remove the given tags instead of adding them
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Move a service provider into a group, or out of its group
+    Group {
+        /// Provider to move
+        #[arg(add = ArgValueCandidates::new(provider_candidates))]
+        name: String,
+        /// Group name, leave out to remove the provider from its group
+        #[arg(add = ArgValueCandidates::new(group_candidates))]
+        group: Option<String>,
+        /// Make the provider the group's default member
+        #[arg(long, requires = "group")]
+        default: bool,
     },
 
     /// Check current environment variable status
     Check,
 
+    /// Print the name of the active service provider
+    Current,
+
+    /// Print the active provider as a shell prompt segment
+    Prompt {
+        /// Segment template, placeholders: {name}, {model}, {url}, {host}; [...] is dropped when one is empty
+        #[arg(short, long, default_value = "{name}")]
+        template: String,
+        /// Only read CCE_PROVIDER from the environment, never the cache file
+        #[arg(long)]
+        env_only: bool,
+    },
+
     /// Output shell integration function
     Shellenv,
 
@@ -61,6 +212,74 @@ pub enum Commands {
 
     /// Launch interactive TUI (Text User Interface)
     Tui,
+
+    /// Print the shell completion script, including provider names from config.toml
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: CompletionShell,
+    },
+
+    /// Export service providers as a shareable TOML bundle
+    Export {
+        /// Leave tokens out of the bundle so it can be shared safely
+        #[arg(long)]
+        redact_tokens: bool,
+        /// Comma-separated list of providers to export (default: all)
+        #[arg(
+            long,
+            value_delimiter = ',',
+            add = ArgValueCandidates::new(provider_candidates)
+        )]
+        providers: Vec<String>,
+    },
+
+    /// Import service providers from a TOML bundle
+    Import {
+        /// Bundle file to import ("-" reads from stdin)
+        file: PathBuf,
+        /// Merge into the existing providers (default, overrides an earlier --replace)
+        #[arg(long, overrides_with = "replace")]
+        merge: bool,
+        /// Replace all existing providers with the bundle contents
+        #[arg(long, overrides_with = "merge")]
+        replace: bool,
+        /// How to handle providers that already exist when merging
+        #[arg(long, value_enum, default_value_t = ConflictPolicy::Ask, conflicts_with = "replace")]
+        on_conflict: ConflictPolicy,
+    },
+}
+
+/// Rendering of command output
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored output for people
+    Text,
+    /// Uncolored, line-oriented output for scripts
+    Plain,
+    Json,
+    Yaml,
+}
+
+/// Shells `cce completions` can register with
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+    Powershell,
+}
+
+/// Resolution strategy for providers that exist both locally and in a bundle
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// Prompt for each conflicting provider (falls back to skip without a terminal)
+    Ask,
+    /// Keep the local provider
+    Skip,
+    /// Take the provider from the bundle
+    Overwrite,
 }
 
 impl Cli {
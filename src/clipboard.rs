@@ -0,0 +1,21 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::io::Write;
+
+/// Ask the terminal to put `text` on the system clipboard with an OSC 52 sequence.
+///
+/// The terminal does the copying, so this also works over SSH. Inside tmux the
+/// sequence is wrapped for passthrough; tmux needs `set-clipboard` enabled.
+pub fn copy(text: &str) -> Result<()> {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    };
+
+    let mut stdout = std::io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use crate::cli::{Cli, CompletionShell};
+use crate::config::Config;
+use crate::trash;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, ValueEnum};
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{CompleteEnv, Shells};
+
+/// Variable the registration scripts set when asking `cce` for completions
+const COMPLETE_VAR: &str = "CCE_COMPLETE";
+
+/// Answer a completion request from the shell and exit, if this run is one
+pub fn handle_completion_request() {
+    CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+/// Print the script that registers completions for `shell`
+pub fn print_registration(shell: CompletionShell) -> Result<()> {
+    write_registration(shell, &mut std::io::stdout())
+}
+
+fn write_registration(shell: CompletionShell, out: &mut dyn std::io::Write) -> Result<()> {
+    let name = shell_name(shell);
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(name)
+        .with_context(|| format!("Completions are not supported for {}", name))?;
+
+    // Completions call back into this binary, so they keep working when `cce` is wrapped
+    let current_exe = std::env::current_exe().unwrap_or_else(|_| "cce".into());
+    completer
+        .write_registration(
+            COMPLETE_VAR,
+            "cce",
+            "cce",
+            &current_exe.to_string_lossy(),
+            out,
+        )
+        .with_context(|| "Failed to write completion script")
+}
+
+/// Profile line that loads completions for the shell named `shell`
+pub fn profile_line(shell: &str) -> Option<String> {
+    let shell = CompletionShell::from_str(shell, true).ok()?;
+    let name = shell_name(shell);
+    Some(match shell {
+        CompletionShell::Fish => format!("cce completions {} | source", name),
+        CompletionShell::Powershell => {
+            format!("cce completions {} | Out-String | Invoke-Expression", name)
+        }
+        _ => format!(r#"eval "$(cce completions {})""#, name),
+    })
+}
+
+fn shell_name(shell: CompletionShell) -> &'static str {
+    match shell {
+        CompletionShell::Bash => "bash",
+        CompletionShell::Zsh => "zsh",
+        CompletionShell::Fish => "fish",
+        CompletionShell::Elvish => "elvish",
+        CompletionShell::Powershell => "powershell",
+    }
+}
+
+/// Provider names from `config.toml`, described by their API URL
+pub fn provider_candidates() -> Vec<CompletionCandidate> {
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    let mut providers: Vec<_> = config.providers.into_values().collect();
+    providers.sort_by(|a, b| a.name.cmp(&b.name));
+    providers
+        .into_iter()
+        .map(|provider| {
+            let help = (!provider.api_url.is_empty()).then(|| provider.api_url.into());
+            CompletionCandidate::new(provider.name).help(help)
+        })
+        .collect()
+}
+
+/// Group names from `config.toml`
+pub fn group_candidates() -> Vec<CompletionCandidate> {
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    let mut groups: Vec<String> = config
+        .providers
+        .into_values()
+        .filter_map(|provider| provider.group)
+        .collect();
+    groups.sort();
+    groups.dedup();
+    groups.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Names of deleted providers in the trash, most recent first
+pub fn deleted_candidates() -> Vec<CompletionCandidate> {
+    trash::names()
+        .unwrap_or_default()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(shell: CompletionShell) -> String {
+        let mut script = Vec::new();
+        write_registration(shell, &mut script).unwrap();
+        String::from_utf8(script).unwrap()
+    }
+
+    #[test]
+    fn bash_registration_calls_back_with_the_cce_variable() {
+        let script = registration(CompletionShell::Bash);
+        let exe = std::env::current_exe().unwrap();
+        assert!(script.contains("CCE_COMPLETE=\"bash\""), "{}", script);
+        assert!(script.contains(&*exe.to_string_lossy()), "{}", script);
+        assert!(script.contains("-F _clap_complete_cce cce"));
+
+        let status = std::process::Command::new("bash")
+            .args(["-n", "-c", &script])
+            .status()
+            .unwrap();
+        assert!(status.success(), "bash rejected the script:\n{}", script);
+    }
+
+    #[test]
+    fn every_shell_has_a_registration() {
+        for shell in CompletionShell::value_variants() {
+            assert!(!registration(*shell).is_empty());
+        }
+    }
+}
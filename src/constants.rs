@@ -1,70 +1,148 @@
 /// Environment variable names used by CCE
 pub const ENV_AUTH_TOKEN: &str = "ANTHROPIC_AUTH_TOKEN";
+pub const ENV_API_KEY: &str = "ANTHROPIC_API_KEY";
 pub const ENV_BASE_URL: &str = "ANTHROPIC_BASE_URL";
 pub const ENV_MODEL: &str = "ANTHROPIC_MODEL";
 pub const ENV_DEFAULT_OPUS_MODEL: &str = "ANTHROPIC_DEFAULT_OPUS_MODEL";
 pub const ENV_DEFAULT_SONNET_MODEL: &str = "ANTHROPIC_DEFAULT_SONNET_MODEL";
 pub const ENV_DEFAULT_HAIKU_MODEL: &str = "ANTHROPIC_DEFAULT_HAIKU_MODEL";
 
+/// Name of the provider exported into the shell, read by `cce prompt`
+pub const ENV_PROVIDER: &str = "CCE_PROVIDER";
+
 /// Control variable for shell integration
 pub const ENV_SHELL_INTEGRATION: &str = "CCE_SHELL_INTEGRATION";
 
+/// File the shell wrapper hands to `cce tui` for the commands to eval once it exits
+pub const ENV_TUI_HANDOFF: &str = "CCE_TUI_HANDOFF";
+
+/// Every variable CCE may set, in export order
+const MANAGED_ENV_VARS: [&str; 8] = [
+    ENV_AUTH_TOKEN,
+    ENV_API_KEY,
+    ENV_BASE_URL,
+    ENV_PROVIDER,
+    ENV_MODEL,
+    ENV_DEFAULT_OPUS_MODEL,
+    ENV_DEFAULT_SONNET_MODEL,
+    ENV_DEFAULT_HAIKU_MODEL,
+];
+
+/// The subset of managed variables that select models
+const MODEL_ENV_VARS: [&str; 4] = [
+    ENV_MODEL,
+    ENV_DEFAULT_OPUS_MODEL,
+    ENV_DEFAULT_SONNET_MODEL,
+    ENV_DEFAULT_HAIKU_MODEL,
+];
+
 /// Helper functions for environment variable management
 use crate::config::Provider;
 
-/// Set all environment variables for a provider
-pub fn set_provider_env_vars(provider: &Provider) {
-    std::env::set_var(ENV_AUTH_TOKEN, &provider.token);
-    std::env::set_var(ENV_BASE_URL, &provider.api_url);
+/// Variables a provider sets and their values; managed variables missing here are unset
+fn provider_env_vars(provider: &Provider) -> Vec<(&'static str, &str)> {
+    let mut vars = vec![
+        (provider.auth_style().env_var(), provider.token.as_str()),
+        (ENV_BASE_URL, provider.api_url.as_str()),
+        (ENV_PROVIDER, provider.name.as_str()),
+    ];
 
     if let Some(ref model) = provider.model {
-        std::env::set_var(ENV_MODEL, model);
-        std::env::set_var(ENV_DEFAULT_OPUS_MODEL, model);
-        std::env::set_var(ENV_DEFAULT_SONNET_MODEL, model);
-        std::env::set_var(ENV_DEFAULT_HAIKU_MODEL, model);
+        vars.push((ENV_MODEL, model.as_str()));
+    }
+    let tiers = [
+        (ENV_DEFAULT_OPUS_MODEL, &provider.opus_model),
+        (ENV_DEFAULT_SONNET_MODEL, &provider.sonnet_model),
+        (ENV_DEFAULT_HAIKU_MODEL, &provider.haiku_model),
+    ];
+    for (var, tier_model) in tiers {
+        // Tiers without their own model follow the provider's main model
+        if let Some(model) = tier_model.as_ref().or(provider.model.as_ref()) {
+            vars.push((var, model.as_str()));
+        }
+    }
+
+    vars
+}
+
+/// Set all environment variables for a provider
+pub fn set_provider_env_vars(provider: &Provider) {
+    let vars = provider_env_vars(provider);
+    for var in MANAGED_ENV_VARS {
+        match vars.iter().find(|(name, _)| *name == var) {
+            Some((_, value)) => std::env::set_var(var, value),
+            None => std::env::remove_var(var),
+        }
     }
 }
 
 /// Clear all environment variables managed by CCE
 pub fn clear_all_env_vars() {
-    std::env::remove_var(ENV_AUTH_TOKEN);
-    std::env::remove_var(ENV_BASE_URL);
-    std::env::remove_var(ENV_MODEL);
-    std::env::remove_var(ENV_DEFAULT_OPUS_MODEL);
-    std::env::remove_var(ENV_DEFAULT_SONNET_MODEL);
-    std::env::remove_var(ENV_DEFAULT_HAIKU_MODEL);
+    for var in MANAGED_ENV_VARS {
+        std::env::remove_var(var);
+    }
+}
+
+/// Quote a value for `eval`: single quotes, with each `'` written as `'\''`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
 }
 
 /// Generate export commands for shell integration
 pub fn generate_export_commands(provider: &Provider) -> String {
-    let mut commands = Vec::new();
-    commands.push(format!("export {}=\"{}\"", ENV_AUTH_TOKEN, provider.token));
-    commands.push(format!("export {}=\"{}\"", ENV_BASE_URL, provider.api_url));
+    let vars = provider_env_vars(provider);
+    let mut commands: Vec<String> = vars
+        .iter()
+        .map(|(var, value)| format!("export {}={}", var, shell_quote(value)))
+        .collect();
 
-    if let Some(ref model) = provider.model {
-        commands.push(format!("export {}=\"{}\"", ENV_MODEL, model));
-        commands.push(format!("export {}=\"{}\"", ENV_DEFAULT_OPUS_MODEL, model));
-        commands.push(format!("export {}=\"{}\"", ENV_DEFAULT_SONNET_MODEL, model));
-        commands.push(format!("export {}=\"{}\"", ENV_DEFAULT_HAIKU_MODEL, model));
-    }
+    // Drop leftovers from the previous provider, e.g. the other auth variable
+    commands.extend(
+        MANAGED_ENV_VARS
+            .into_iter()
+            .filter(|var| !vars.iter().any(|(name, _)| name == var))
+            .map(|var| format!("unset {}", var)),
+    );
 
     commands.join("\n")
 }
 
+/// Generate export commands for the model variables only, leaving the provider in place
+pub fn generate_model_export_commands(provider: &Provider) -> String {
+    let vars = provider_env_vars(provider);
+    MODEL_ENV_VARS
+        .into_iter()
+        .map(|var| match vars.iter().find(|(name, _)| *name == var) {
+            Some((_, value)) => format!("export {}={}", var, shell_quote(value)),
+            None => format!("unset {}", var),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Generate unset commands for shell integration
 pub fn generate_unset_commands() -> String {
-    let env_vars = [
-        ENV_AUTH_TOKEN,
-        ENV_BASE_URL,
-        ENV_MODEL,
-        ENV_DEFAULT_OPUS_MODEL,
-        ENV_DEFAULT_SONNET_MODEL,
-        ENV_DEFAULT_HAIKU_MODEL,
-    ];
-
-    env_vars
-        .iter()
+    MANAGED_ENV_VARS
+        .into_iter()
         .map(|var| format!("unset {}", var))
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exported_values_survive_eval() {
+        let value = r#"it's "$(touch /tmp/x)" `id` \n"#;
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg("eval \"$1\"; printf %s \"$V\"")
+            .arg("sh")
+            .arg(format!("export V={}", shell_quote(value)))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), value);
+    }
+}
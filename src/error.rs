@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// Exit code for failures without a more specific kind
+pub const EXIT_FAILURE: i32 = 1;
+/// A named provider, group or active provider is missing (2 is taken by usage errors)
+pub const EXIT_NOT_FOUND: i32 = 3;
+/// `config.toml` or a bundle cannot be parsed or is inconsistent
+pub const EXIT_CONFIG_INVALID: i32 = 4;
+/// The environment does not match the selected provider
+pub const EXIT_ENV_MISMATCH: i32 = 5;
+/// Reading or writing a file or the terminal failed
+pub const EXIT_IO: i32 = 6;
+
+/// Failures scripts may want to tell apart by exit code
+#[derive(Debug)]
+pub enum CceError {
+    NotFound(String),
+    ConfigInvalid(String),
+    EnvMismatch(String),
+}
+
+impl CceError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CceError::NotFound(_) => EXIT_NOT_FOUND,
+            CceError::ConfigInvalid(_) => EXIT_CONFIG_INVALID,
+            CceError::EnvMismatch(_) => EXIT_ENV_MISMATCH,
+        }
+    }
+}
+
+impl fmt::Display for CceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CceError::NotFound(message)
+            | CceError::ConfigInvalid(message)
+            | CceError::EnvMismatch(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for CceError {}
+
+/// Exit code for an error, looking through any context added on the way up
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<CceError>() {
+            return err.exit_code();
+        }
+        if cause.is::<toml::de::Error>() {
+            return EXIT_CONFIG_INVALID;
+        }
+        if cause.is::<std::io::Error>() {
+            return EXIT_IO;
+        }
+    }
+    EXIT_FAILURE
+}
@@ -0,0 +1,107 @@
+/// How well a query matched a candidate
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Character indices of the candidate that matched the query
+    pub positions: Vec<usize>,
+}
+
+/// Match `query` against `candidate` as a case-insensitive subsequence.
+///
+/// Consecutive characters and matches at word starts score higher, gaps score lower.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut positions = Vec::new();
+    let mut previous: Option<char> = None;
+
+    for (index, c) in candidate.chars().enumerate() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(wanted)) {
+            let word_start = previous.is_none_or(|p| !p.is_alphanumeric());
+            let consecutive = positions.last().is_some_and(|&last| last + 1 == index);
+            score += 1;
+            if word_start {
+                score += 8;
+            }
+            if consecutive {
+                score += 5;
+            } else if let Some(&last) = positions.last() {
+                score -= (index - last - 1) as i64;
+            } else {
+                score -= index as i64;
+            }
+            positions.push(index);
+            query.next();
+        }
+        previous = Some(c);
+    }
+
+    query
+        .peek()
+        .is_none()
+        .then_some(FuzzyMatch { score, positions })
+}
+
+/// Indices of `items` matching `query` with their matches, best first.
+/// Equal scores keep the order of `items`.
+pub fn filter<T>(query: &str, items: &[T], key: impl Fn(&T) -> &str) -> Vec<(usize, FuzzyMatch)> {
+    let mut matches: Vec<(usize, FuzzyMatch)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| fuzzy_match(query, key(item)).map(|m| (index, m)))
+        .collect();
+    matches.sort_by(|(a_index, a), (b_index, b)| b.score.cmp(&a.score).then(a_index.cmp(b_index)));
+    matches
+}
+
+/// Candidates within a small edit distance of `name`, closest first
+pub fn suggestions<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let name = name.to_lowercase();
+    let mut close: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance = strsim::levenshtein(&name, &candidate.to_lowercase());
+            let limit = (name.chars().count().max(candidate.chars().count()) / 3).max(1);
+            (distance <= limit).then_some((distance, candidate))
+        })
+        .collect();
+    close.sort();
+    close
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequences_ignoring_case() {
+        let found = fuzzy_match("DSK", "deepseek").unwrap();
+        assert_eq!(found.positions, [0, 4, 7]);
+        assert!(fuzzy_match("ksd", "deepseek").is_none());
+        assert!(fuzzy_match("", "anything").is_some());
+    }
+
+    #[test]
+    fn word_starts_and_runs_rank_first() {
+        let names = ["kimi-moonshot", "minimax", "moonshot"];
+        let ranked: Vec<&str> = filter("moon", &names, |name| name)
+            .into_iter()
+            .map(|(index, _)| names[index])
+            .collect();
+        assert_eq!(ranked, ["moonshot", "kimi-moonshot"]);
+    }
+
+    #[test]
+    fn suggests_close_names_only() {
+        let names = ["deepseek", "kimi", "anthropic"];
+        assert_eq!(suggestions("deepsek", names), ["deepseek"]);
+        assert_eq!(suggestions("KIMI", names), ["kimi"]);
+        assert!(suggestions("openrouter", names).is_empty());
+    }
+}
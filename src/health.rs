@@ -0,0 +1,44 @@
+use crate::config::Provider;
+use crate::models;
+use std::time::{Duration, Instant};
+
+/// Probes give up after this long, so a dead gateway shows up quickly
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What probing a gateway found
+#[derive(Clone)]
+pub enum Health {
+    /// The probe has not finished yet
+    Checking,
+    /// The gateway answered the request, or is there but does not list models
+    Up { latency: Duration },
+    /// The gateway answered with an error status, e.g. 401 for a rejected token
+    Rejected { status: u16, latency: Duration },
+    /// The gateway could not be reached
+    Down(String),
+}
+
+/// Ask the gateway for a single model to see whether it is up and takes the credentials.
+/// Blocks for up to `PROBE_TIMEOUT`, so callers run it off the UI thread.
+pub fn probe(provider: &Provider) -> Health {
+    let agent = ureq::AgentBuilder::new().timeout(PROBE_TIMEOUT).build();
+    let started = Instant::now();
+    match models::models_request(&agent, provider)
+        .query("limit", "1")
+        .call()
+    {
+        Ok(_) => Health::Up {
+            latency: started.elapsed(),
+        },
+        // Gateways such as Ollama or LiteLLM do not serve the endpoint, so these only
+        // tell that the gateway is up; the token goes unchecked
+        Err(ureq::Error::Status(404 | 405, _)) => Health::Up {
+            latency: started.elapsed(),
+        },
+        Err(ureq::Error::Status(status, _)) => Health::Rejected {
+            status,
+            latency: started.elapsed(),
+        },
+        Err(ureq::Error::Transport(transport)) => Health::Down(transport.kind().to_string()),
+    }
+}
@@ -0,0 +1,147 @@
+use crate::config::Config;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tab-separated switch log under the config directory, oldest first
+const HISTORY_FILE: &str = "history";
+
+/// Entries kept on disk; older switches are dropped
+const MAX_ENTRIES: usize = 500;
+
+/// Where a switch took effect
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Through the shell integration, exported into the calling shell
+    Session,
+    /// Only the persisted default, e.g. from the TUI or without shell integration
+    Global,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Session => "session",
+            Scope::Global => "global",
+        }
+    }
+}
+
+/// One switch to a provider
+#[derive(Serialize, Clone)]
+pub struct HistoryEntry {
+    /// Unix timestamp of the switch
+    pub timestamp: u64,
+    pub provider: String,
+    pub scope: Scope,
+}
+
+impl HistoryEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(3, '\t');
+        let timestamp = fields.next()?.parse().ok()?;
+        let scope = match fields.next()? {
+            "session" => Scope::Session,
+            "global" => Scope::Global,
+            _ => return None,
+        };
+        let provider = fields.next()?.to_string();
+        Some(Self {
+            timestamp,
+            provider,
+            scope,
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}",
+            self.timestamp,
+            self.scope.as_str(),
+            self.provider
+        )
+    }
+}
+
+/// All recorded switches, oldest first
+pub fn load() -> Vec<HistoryEntry> {
+    Config::config_dir()
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join(HISTORY_FILE)).ok())
+        .map(|content| content.lines().filter_map(HistoryEntry::parse).collect())
+        .unwrap_or_default()
+}
+
+/// Record a switch to `provider`, unless it is already the latest entry
+pub fn record(provider: &str, scope: Scope) -> Result<()> {
+    let mut entries = load();
+    // New shells re-select the default on startup, which is not a switch
+    if entries.last().is_some_and(|last| last.provider == provider) {
+        return Ok(());
+    }
+
+    entries.push(HistoryEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        provider: provider.to_string(),
+        scope,
+    });
+    let skip = entries.len().saturating_sub(MAX_ENTRIES);
+
+    let dir = Config::config_dir()?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create config directory: {:?}", dir))?;
+    let path = dir.join(HISTORY_FILE);
+    let content: String = entries[skip..]
+        .iter()
+        .map(|entry| entry.to_line() + "\n")
+        .collect();
+    fs::write(&path, content).with_context(|| format!("Failed to write history: {:?}", path))
+}
+
+/// Distinct providers from newest to oldest switch
+pub fn recent_providers(entries: &[HistoryEntry]) -> Vec<&str> {
+    let mut recent: Vec<&str> = Vec::new();
+    for entry in entries.iter().rev() {
+        if !recent.contains(&entry.provider.as_str()) {
+            recent.push(&entry.provider);
+        }
+    }
+    recent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestHome;
+
+    #[test]
+    fn lines_round_trip_and_bad_ones_are_skipped() {
+        let entry = HistoryEntry::parse("1760000000\tsession\tmy\tprovider").unwrap();
+        assert_eq!(entry.timestamp, 1760000000);
+        assert!(entry.scope == Scope::Session);
+        assert_eq!(entry.provider, "my\tprovider");
+        assert_eq!(entry.to_line(), "1760000000\tsession\tmy\tprovider");
+
+        assert!(HistoryEntry::parse("soon\tglobal\tkimi").is_none());
+        assert!(HistoryEntry::parse("1760000000\tlocal\tkimi").is_none());
+        assert!(HistoryEntry::parse("1760000000\tglobal").is_none());
+    }
+
+    #[test]
+    fn records_switches_and_lists_recent_providers() {
+        let _home = TestHome::new();
+        for provider in ["kimi", "kimi", "deepseek", "kimi", "glm"] {
+            record(provider, Scope::Global).unwrap();
+        }
+
+        let entries = load();
+        // Selecting the same provider again is not a switch
+        assert_eq!(entries.len(), 4);
+        assert_eq!(recent_providers(&entries), ["glm", "kimi", "deepseek"]);
+    }
+}
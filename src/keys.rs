@@ -0,0 +1,357 @@
+use crate::config::KeyList;
+use crate::error::CceError;
+use crate::theme;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Something a key does in the TUI provider list, named as in `[tui.keys]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Use,
+    Search,
+    Add,
+    Model,
+    Delete,
+    Clear,
+    Undo,
+    FilterTag,
+    Test,
+    Reveal,
+    CopyToken,
+    CopyUrl,
+    CopyExports,
+    Help,
+    Quit,
+}
+
+impl Action {
+    /// Every action, in the order the help overlay lists them
+    pub const ALL: [Action; 17] = [
+        Action::Up,
+        Action::Down,
+        Action::Use,
+        Action::Search,
+        Action::Add,
+        Action::Model,
+        Action::Delete,
+        Action::Clear,
+        Action::Undo,
+        Action::FilterTag,
+        Action::Test,
+        Action::Reveal,
+        Action::CopyToken,
+        Action::CopyUrl,
+        Action::CopyExports,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Use => "use",
+            Action::Search => "search",
+            Action::Add => "add",
+            Action::Model => "model",
+            Action::Delete => "delete",
+            Action::Clear => "clear",
+            Action::Undo => "undo",
+            Action::FilterTag => "filter_tag",
+            Action::Test => "test",
+            Action::Reveal => "reveal",
+            Action::CopyToken => "copy_token",
+            Action::CopyUrl => "copy_url",
+            Action::CopyExports => "copy_exports",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+            Action::Use => "Use the selected provider",
+            Action::Search => "Search providers",
+            Action::Add => "Add a provider",
+            Action::Model => "Pick a model for the selected provider",
+            Action::Delete => "Delete the selected provider",
+            Action::Clear => "Clear the current provider",
+            Action::Undo => "Undo the last delete, switch or clear",
+            Action::FilterTag => "Cycle the tag filter",
+            Action::Test => "Probe all gateways again",
+            Action::Reveal => "Show the full token for a few seconds",
+            Action::CopyToken => "Copy the token",
+            Action::CopyUrl => "Copy the API URL",
+            Action::CopyExports => "Copy the export commands",
+            Action::Help => "Show this help",
+            Action::Quit => "Quit",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["up", "k"],
+            Action::Down => &["down", "j"],
+            Action::Use => &["enter", "u"],
+            Action::Search => &["/"],
+            Action::Add => &["a"],
+            Action::Model => &["m"],
+            Action::Delete => &["d"],
+            Action::Clear => &["c"],
+            Action::Undo => &["ctrl-z", "U"],
+            Action::FilterTag => &["f"],
+            Action::Test => &["t"],
+            Action::Reveal => &["r"],
+            Action::CopyToken => &["y"],
+            Action::CopyUrl => &["Y"],
+            Action::CopyExports => &["e"],
+            Action::Help => &["?", "f1"],
+            Action::Quit => &["q", "esc"],
+        }
+    }
+}
+
+/// A key with the modifiers that matter for telling bindings apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    /// Only Ctrl and Alt; Shift is already part of the character or `BackTab`
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            code,
+            modifiers: modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    /// Parse names such as `j`, `U`, `enter`, `ctrl-n`, `alt+x` or `shift-tab`
+    fn parse(spec: &str) -> Option<Self> {
+        let mut rest = spec;
+        let mut modifiers = KeyModifiers::NONE;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            let modifier = [
+                ("ctrl", KeyModifiers::CONTROL),
+                ("alt", KeyModifiers::ALT),
+                ("shift", KeyModifiers::SHIFT),
+            ]
+            .into_iter()
+            .find(|(name, _)| {
+                lower.len() > name.len() + 1
+                    && lower.starts_with(name)
+                    && matches!(lower.as_bytes()[name.len()], b'-' | b'+')
+            });
+            match modifier {
+                Some((name, modifier)) => {
+                    modifiers |= modifier;
+                    rest = &rest[name.len() + 1..];
+                }
+                None => break,
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            // Terminals report Ctrl and Alt combinations with the lowercase letter
+            (Some(c), None) if !modifiers.is_empty() => KeyCode::Char(c.to_ascii_lowercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                name => {
+                    let number: u8 = name.strip_prefix('f')?.parse().ok()?;
+                    if !(1..=12).contains(&number) {
+                        return None;
+                    }
+                    KeyCode::F(number)
+                }
+            },
+        };
+        Some(Self::new(code, modifiers))
+    }
+
+    /// Keys the list handles before the keymap: 1-9 switch to a recent provider and
+    /// Ctrl+C always quits
+    fn is_reserved(&self) -> bool {
+        matches!(self.code, KeyCode::Char('1'..='9'))
+            || (self.code == KeyCode::Char('c') && self.modifiers.contains(KeyModifiers::CONTROL))
+    }
+
+    /// Whether the key types text into a field rather than issuing a command
+    pub fn is_text(code: KeyCode, modifiers: KeyModifiers) -> bool {
+        matches!(code, KeyCode::Char(_))
+            && !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => f.write_str("Enter"),
+            KeyCode::Esc => f.write_str("Esc"),
+            KeyCode::Tab => f.write_str("Tab"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::Backspace => f.write_str("Backspace"),
+            KeyCode::Delete => f.write_str("Del"),
+            KeyCode::Insert => f.write_str("Insert"),
+            KeyCode::Home => f.write_str("Home"),
+            KeyCode::End => f.write_str("End"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            KeyCode::Up => write!(f, "{}", theme::ARROW_UP),
+            KeyCode::Down => write!(f, "{}", theme::ARROW_DOWN),
+            KeyCode::Left => write!(f, "{}", theme::ARROW_LEFT),
+            KeyCode::Right => write!(f, "{}", theme::ARROW_RIGHT),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Keys for each TUI action: the defaults, with `[tui.keys]` replacing them per action
+pub struct KeyMap {
+    bindings: HashMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let keys = action
+                    .default_keys()
+                    .iter()
+                    .map(|spec| KeyBinding::parse(spec).expect("default key binding"))
+                    .collect();
+                (action, keys)
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    /// Apply the `[tui.keys]` overrides, rejecting unknown actions, bad key names,
+    /// reserved keys and keys bound to two actions
+    pub fn from_config(overrides: &BTreeMap<String, KeyList>) -> Result<Self> {
+        let invalid = |message: String| CceError::ConfigInvalid(format!("[tui.keys]: {}", message));
+
+        let mut keymap = Self::default();
+        for (name, keys) in overrides {
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == name)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = Action::ALL.iter().map(Action::name).collect();
+                    invalid(format!(
+                        "unknown action '{}', expected one of: {}",
+                        name,
+                        names.join(", ")
+                    ))
+                })?;
+            let bindings = keys
+                .as_slice()
+                .iter()
+                .map(|spec| {
+                    let binding = KeyBinding::parse(spec)
+                        .ok_or_else(|| invalid(format!("invalid key '{}' for '{}'", spec, name)))?;
+                    if binding.is_reserved() {
+                        return Err(invalid(format!(
+                            "key '{}' for '{}' is reserved: 1-9 switch to recent providers \
+                             and Ctrl+C quits",
+                            binding, name
+                        )));
+                    }
+                    Ok(binding)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            keymap.bindings.insert(action, bindings);
+        }
+
+        let mut seen: Vec<(KeyBinding, Action)> = Vec::new();
+        for action in Action::ALL {
+            for binding in &keymap.bindings[&action] {
+                if let Some((_, other)) = seen.iter().find(|(key, _)| key == binding) {
+                    return Err(invalid(format!(
+                        "key '{}' is bound to both '{}' and '{}'",
+                        binding,
+                        other.name(),
+                        action.name()
+                    ))
+                    .into());
+                }
+                seen.push((*binding, action));
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Action bound to a key press, if any
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        let pressed = KeyBinding::new(code, modifiers);
+        Action::ALL
+            .into_iter()
+            .find(|action| self.bindings[action].contains(&pressed))
+    }
+
+    pub fn matches(&self, action: Action, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        self.bindings[&action].contains(&KeyBinding::new(code, modifiers))
+    }
+
+    /// Keys bound to `action` for display, e.g. `↓/j`; empty when unbound
+    pub fn keys(&self, action: Action) -> String {
+        self.describe(action, true)
+    }
+
+    /// Like `keys`, leaving out plain characters, which type into input fields instead
+    pub fn command_keys(&self, action: Action) -> String {
+        self.describe(action, false)
+    }
+
+    fn describe(&self, action: Action, with_text: bool) -> String {
+        self.bindings[&action]
+            .iter()
+            .filter(|binding| with_text || !KeyBinding::is_text(binding.code, binding.modifiers))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
@@ -0,0 +1,202 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Single-line text field with a cursor, as used by the TUI forms
+#[derive(Default)]
+pub struct LineInput {
+    text: String,
+    /// Cursor position in characters, from 0 to the length of `text`
+    cursor: usize,
+}
+
+impl LineInput {
+    /// Field holding `text` with the cursor at its end
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let cursor = text.chars().count();
+        Self { text, cursor }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Apply an editing key, returning whether it was one
+    pub fn handle_key(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        let control = modifiers.contains(KeyModifiers::CONTROL);
+        match key {
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Backspace => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.text.remove(self.byte_index(self.cursor));
+                }
+            }
+            KeyCode::Delete => {
+                if self.cursor < self.len() {
+                    self.text.remove(self.byte_index(self.cursor));
+                }
+            }
+            KeyCode::Char('a') if control => self.cursor = 0,
+            KeyCode::Char('e') if control => self.cursor = self.len(),
+            KeyCode::Char('w') if control => self.delete_word(),
+            KeyCode::Char('u') if control => {
+                self.text.drain(..self.byte_index(self.cursor));
+                self.cursor = 0;
+            }
+            KeyCode::Char('k') if control => {
+                self.text.truncate(self.byte_index(self.cursor));
+            }
+            KeyCode::Char(c) if !control && !modifiers.contains(KeyModifiers::ALT) => {
+                self.insert_str(&c.to_string());
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Insert text at the cursor, e.g. from a paste; line breaks and other control
+    /// characters are dropped since the field holds a single line
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        self.text.insert_str(self.byte_index(self.cursor), &text);
+        self.cursor += text.chars().count();
+    }
+
+    /// Delete the word before the cursor along with any spaces after it, like Ctrl-W in a shell
+    fn delete_word(&mut self) {
+        let before: Vec<char> = self.text.chars().take(self.cursor).collect();
+        let mut start = before.len();
+        while start > 0 && before[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !before[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        self.text
+            .drain(self.byte_index(start)..self.byte_index(self.cursor));
+        self.cursor = start;
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, chars: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(chars)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut LineInput, key: KeyCode) {
+        assert!(input.handle_key(key, KeyModifiers::NONE));
+    }
+
+    fn ctrl(input: &mut LineInput, c: char) {
+        assert!(input.handle_key(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    #[test]
+    fn moves_the_cursor_and_inserts_at_it() {
+        let mut input = LineInput::new("kimi");
+        assert_eq!(input.cursor(), 4);
+        press(&mut input, KeyCode::Right);
+        assert_eq!(input.cursor(), 4);
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor(), 0);
+        press(&mut input, KeyCode::Char('x'));
+        press(&mut input, KeyCode::End);
+        press(&mut input, KeyCode::Left);
+        press(&mut input, KeyCode::Backspace);
+        press(&mut input, KeyCode::Delete);
+        assert_eq!(input.as_str(), "xki");
+        assert_eq!(input.cursor(), 3);
+
+        ctrl(&mut input, 'a');
+        assert_eq!(input.cursor(), 0);
+        press(&mut input, KeyCode::Right);
+        ctrl(&mut input, 'e');
+        assert_eq!(input.cursor(), 3);
+    }
+
+    #[test]
+    fn deletes_words_and_line_parts_like_a_shell() {
+        let mut input = LineInput::new("claude opus  ");
+        ctrl(&mut input, 'w');
+        assert_eq!(input.as_str(), "claude ");
+        ctrl(&mut input, 'w');
+        assert!(input.is_empty());
+
+        let mut input = LineInput::new("https://api.example.com/v1");
+        for _ in 0..3 {
+            press(&mut input, KeyCode::Left);
+        }
+        ctrl(&mut input, 'k');
+        assert_eq!(input.as_str(), "https://api.example.com");
+        for _ in 0..4 {
+            press(&mut input, KeyCode::Left);
+        }
+        ctrl(&mut input, 'u');
+        assert_eq!(input.as_str(), ".com");
+        assert_eq!(input.cursor(), 0);
+    }
+
+    #[test]
+    fn ignores_modified_characters() {
+        let mut input = LineInput::new("kimi");
+        assert!(!input.handle_key(KeyCode::Char('x'), KeyModifiers::ALT));
+        assert!(!input.handle_key(KeyCode::Char('x'), KeyModifiers::CONTROL));
+        assert!(!input.handle_key(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(input.as_str(), "kimi");
+    }
+
+    #[test]
+    fn paste_drops_line_breaks() {
+        let mut input = LineInput::new("sk-");
+        press(&mut input, KeyCode::Home);
+        input.insert_str("a\r\nb\tc\n");
+        assert_eq!(input.as_str(), "abcsk-");
+        assert_eq!(input.cursor(), 3);
+    }
+
+    #[test]
+    fn edits_multibyte_text_on_character_boundaries() {
+        let mut input = LineInput::new("月之暗面");
+        press(&mut input, KeyCode::Left);
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.as_str(), "月之面");
+        press(&mut input, KeyCode::Char('é'));
+        assert_eq!(input.as_str(), "月之é面");
+        assert_eq!(input.cursor(), 3);
+        press(&mut input, KeyCode::Delete);
+        ctrl(&mut input, 'k');
+        assert_eq!(input.as_str(), "月之é");
+
+        let mut input = LineInput::new("模型 🚀 kimi");
+        ctrl(&mut input, 'w');
+        assert_eq!(input.as_str(), "模型 🚀 ");
+        ctrl(&mut input, 'w');
+        assert_eq!(input.as_str(), "模型 ");
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::Right);
+        input.insert_str("🚀");
+        assert_eq!(input.as_str(), "模🚀型 ");
+    }
+}
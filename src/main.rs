@@ -1,42 +1,169 @@
 mod cli;
+mod clipboard;
+mod completions;
 mod config;
 mod constants;
+mod error;
+mod fuzzy;
+mod health;
+mod history;
+mod keys;
+mod line_input;
+mod models;
+mod output;
+mod picker;
+mod presets;
+mod prompt;
 mod provider;
+mod rules;
+#[cfg(test)]
+mod test_support;
+mod theme;
+mod trash;
 mod tui;
 
 use anyhow::Result;
 use cli::{Cli, Commands};
-use config::Config;
+use config::{Config, Provider};
+use output::OutputOptions;
 use provider::ProviderManager;
 
-fn main() -> Result<()> {
+fn main() {
+    completions::handle_completion_request();
+
+    if let Err(err) = run() {
+        eprintln!("Error: {:?}", err);
+        std::process::exit(error::exit_code(&err));
+    }
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse_args();
+
+    // Prompt segments run on every prompt, so they skip parsing the config
+    if let Commands::Prompt { template, env_only } = &cli.command {
+        return prompt::print_prompt(template, *env_only);
+    }
+    if let Commands::Completions { shell } = cli.command {
+        return completions::print_registration(shell);
+    }
+
     let mut config = Config::load()?;
+    theme::init(&config.theme, cli.no_color);
+    let output = OutputOptions {
+        format: cli.format,
+        show_secrets: cli.show_secrets,
+    };
 
     match cli.command {
-        Commands::List => {
-            ProviderManager::list_providers(&config)?;
+        Commands::List { tag, group } => {
+            ProviderManager::list_providers(&config, &tag, group.as_deref(), &output)?;
         }
 
         Commands::Add {
             name,
             api_url,
             token,
+            token_stdin,
             model,
+            tag,
+            group,
+            extends,
+            preset,
+            force,
+        } => {
+            let provider = Provider {
+                name,
+                api_url: api_url.unwrap_or_default(),
+                token: token.unwrap_or_default(),
+                model,
+                tags: tag,
+                group,
+                extends,
+                ..Default::default()
+            };
+            ProviderManager::add_provider(
+                &mut config,
+                provider,
+                preset.as_deref(),
+                token_stdin,
+                force,
+            )?;
+        }
+
+        Commands::Delete { name, force } => {
+            ProviderManager::remove_provider(&mut config, &name, force)?;
+        }
+
+        Commands::Restore { name } => {
+            ProviderManager::restore_provider(&mut config, &name)?;
+        }
+
+        Commands::Use { name, group } => match (name, group) {
+            (_, Some(group)) => ProviderManager::use_group(&mut config, &group)?,
+            (Some(name), None) if name == "-" => ProviderManager::use_previous(&mut config)?,
+            (Some(name), None) => ProviderManager::use_provider(&mut config, &name)?,
+            (None, None) => ProviderManager::pick_provider(&mut config)?,
+        },
+
+        Commands::Auto {
+            enable,
+            disable,
+            print,
         } => {
-            ProviderManager::add_provider(&mut config, name, api_url, token, model)?;
+            if enable || disable {
+                ProviderManager::set_auto_switch(&mut config, enable)?;
+            } else if print {
+                ProviderManager::print_startup_exports(&config)?;
+            } else {
+                ProviderManager::show_auto_provider(&config, &output)?;
+            }
+        }
+
+        Commands::History { limit } => {
+            ProviderManager::show_history(limit, &output)?;
+        }
+
+        Commands::Presets => {
+            ProviderManager::list_presets(&output)?;
         }
 
-        Commands::Delete { name } => {
-            ProviderManager::remove_provider(&mut config, &name)?;
+        Commands::Model { model, save } => {
+            ProviderManager::switch_model(&mut config, &model, save)?;
         }
 
-        Commands::Use { name } => {
-            ProviderManager::use_provider(&mut config, &name)?;
+        Commands::Models { name, offline } => {
+            ProviderManager::list_models(&config, &name, offline, &output)?;
+        }
+
+        Commands::Show { name, resolved } => {
+            ProviderManager::show_provider(&config, &name, resolved, &output)?;
+        }
+
+        Commands::Tag { name, tags, remove } => {
+            ProviderManager::tag_provider(&mut config, &name, &tags, remove)?;
+        }
+
+        Commands::Group {
+            name,
+            group,
+            default,
+        } => {
+            ProviderManager::set_provider_group(&mut config, &name, group, default)?;
         }
 
         Commands::Check => {
-            ProviderManager::check_environment(&config)?;
+            ProviderManager::check_environment(&config, &output)?;
+        }
+
+        Commands::Current => {
+            ProviderManager::show_current(&config, &output)?;
+        }
+
+        Commands::Prompt { .. } => unreachable!("prompt is handled before loading the config"),
+
+        Commands::Completions { .. } => {
+            unreachable!("completions are handled before loading the config")
         }
 
         Commands::Shellenv => {
@@ -54,6 +181,24 @@ fn main() -> Result<()> {
         Commands::Tui => {
             tui::run_tui(config)?;
         }
+
+        Commands::Export {
+            redact_tokens,
+            providers,
+        } => {
+            ProviderManager::export_providers(&config, &providers, redact_tokens)?;
+        }
+
+        Commands::Import {
+            file,
+            merge,
+            replace,
+            on_conflict,
+        } => {
+            // clap keeps only the last of --merge and --replace
+            let replace = replace && !merge;
+            ProviderManager::import_providers(&mut config, &file, replace, on_conflict)?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,170 @@
+use crate::config::{AuthStyle, Config, Provider};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Cache of model lists keyed by gateway URL, so the pickers keep working offline
+const CACHE_FILE: &str = "models_cache.toml";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound on followed pages, in case a gateway keeps reporting `has_more`
+const MAX_PAGES: usize = 20;
+
+/// Models available on a gateway and where the list came from
+pub struct ModelList {
+    pub models: Vec<String>,
+    /// Unix timestamp of the fetch that produced the list
+    pub fetched_at: u64,
+    /// Set when the gateway could not be reached and the cache was used instead
+    pub stale_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ModelCache {
+    #[serde(default)]
+    gateways: BTreeMap<String, CachedModels>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedModels {
+    fetched_at: u64,
+    models: Vec<String>,
+}
+
+/// Page of the `/v1/models` response; Anthropic and OpenAI-style gateways share `data[].id`
+#[derive(Deserialize)]
+struct ModelsPage {
+    data: Vec<ModelEntry>,
+    #[serde(default)]
+    has_more: bool,
+    #[serde(default)]
+    last_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+impl ModelCache {
+    fn load() -> Self {
+        Config::config_dir()
+            .ok()
+            .and_then(|dir| fs::read_to_string(dir.join(CACHE_FILE)).ok())
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<()> {
+        let dir = Config::config_dir()?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create config directory: {:?}", dir))?;
+        let path = dir.join(CACHE_FILE);
+        let content =
+            toml::to_string_pretty(self).with_context(|| "Failed to serialize model cache")?;
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write model cache: {:?}", path))
+    }
+}
+
+/// `GET /v1/models` on the gateway behind `provider`, with its credentials
+pub fn models_request(agent: &ureq::Agent, provider: &Provider) -> ureq::Request {
+    let endpoint = format!("{}/v1/models", provider.api_url.trim_end_matches('/'));
+    let request = agent.get(&endpoint).set("anthropic-version", "2023-06-01");
+    match provider.auth_style() {
+        AuthStyle::AuthToken => request.set("Authorization", &format!("Bearer {}", provider.token)),
+        AuthStyle::ApiKey => request.set("x-api-key", &provider.token),
+    }
+}
+
+/// Ask the gateway behind `provider` for its models
+pub fn fetch_models(provider: &Provider) -> Result<Vec<String>> {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let endpoint = format!("{}/v1/models", provider.api_url.trim_end_matches('/'));
+
+    let mut models = Vec::new();
+    let mut after_id: Option<String> = None;
+    for _ in 0..MAX_PAGES {
+        let mut request = models_request(&agent, provider).query("limit", "1000");
+        if let Some(ref after_id) = after_id {
+            request = request.query("after_id", after_id);
+        }
+
+        let body = match request.call() {
+            Ok(response) => response
+                .into_string()
+                .with_context(|| format!("Failed to read response from {}", endpoint))?,
+            Err(ureq::Error::Status(code, _)) => {
+                bail!("{} answered with HTTP {}", endpoint, code)
+            }
+            Err(ureq::Error::Transport(transport)) => match transport.message() {
+                Some(message) => bail!(
+                    "Failed to reach {}: {} ({})",
+                    endpoint,
+                    transport.kind(),
+                    message
+                ),
+                None => bail!("Failed to reach {}: {}", endpoint, transport.kind()),
+            },
+        };
+        let page: ModelsPage = serde_json::from_str(&body)
+            .with_context(|| format!("Unexpected response from {}", endpoint))?;
+
+        models.extend(page.data.into_iter().map(|entry| entry.id));
+        match (page.has_more, page.last_id) {
+            (true, Some(last_id)) => after_id = Some(last_id),
+            _ => break,
+        }
+    }
+
+    models.sort();
+    models.dedup();
+    Ok(models)
+}
+
+/// Fetch the models for `provider`, falling back to the cache when the gateway is unreachable.
+/// With `offline`, only the cache is consulted.
+pub fn load_models(provider: &Provider, offline: bool) -> Result<ModelList> {
+    let mut cache = ModelCache::load();
+
+    let error = if offline {
+        "offline mode".to_string()
+    } else {
+        match fetch_models(provider) {
+            Ok(models) => {
+                let fetched_at = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_secs())
+                    .unwrap_or_default();
+                cache.gateways.insert(
+                    provider.api_url.clone(),
+                    CachedModels {
+                        fetched_at,
+                        models: models.clone(),
+                    },
+                );
+                // The fresh list is still useful if the cache cannot be written
+                let _ = cache.save();
+                return Ok(ModelList {
+                    models,
+                    fetched_at,
+                    stale_reason: None,
+                });
+            }
+            Err(err) => err.to_string(),
+        }
+    };
+
+    match cache.gateways.remove(&provider.api_url) {
+        Some(cached) => Ok(ModelList {
+            models: cached.models,
+            fetched_at: cached.fetched_at,
+            stale_reason: Some(error),
+        }),
+        None if offline => bail!("No cached models for {}", provider.api_url),
+        None => bail!("{}", error),
+    }
+}
@@ -0,0 +1,183 @@
+use crate::cli::OutputFormat;
+use crate::config::Provider;
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// How command results should be rendered
+#[derive(Clone, Copy)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    pub show_secrets: bool,
+}
+
+impl OutputOptions {
+    /// Whether the output is meant for other programs rather than people
+    pub fn is_structured(&self) -> bool {
+        matches!(self.format, OutputFormat::Json | OutputFormat::Yaml)
+    }
+
+    /// Render a token for display, masking it unless secrets were requested
+    pub fn token(&self, token: &str) -> String {
+        if self.show_secrets {
+            token.to_string()
+        } else {
+            mask_token(token)
+        }
+    }
+
+    /// Print a value as JSON or YAML
+    pub fn emit<T: Serialize>(&self, value: &T) -> Result<()> {
+        let rendered = match self.format {
+            OutputFormat::Yaml => {
+                serde_yaml::to_string(value).with_context(|| "Failed to serialize output")?
+            }
+            _ => {
+                serde_json::to_string_pretty(value).with_context(|| "Failed to serialize output")?
+            }
+        };
+        println!("{}", rendered.trim_end());
+        Ok(())
+    }
+}
+
+/// Keep the first 8 characters of a token and hide the rest
+pub fn mask_token(token: &str) -> String {
+    if token.chars().count() > 8 {
+        format!("{}****", token.chars().take(8).collect::<String>())
+    } else {
+        "****".to_string()
+    }
+}
+
+/// Provider as shown by `list` and `current`
+#[derive(Serialize)]
+pub struct ProviderView {
+    pub name: String,
+    pub api_url: String,
+    pub token: Option<String>,
+    pub model: Option<String>,
+    pub opus_model: Option<String>,
+    pub sonnet_model: Option<String>,
+    pub haiku_model: Option<String>,
+    /// Variable that receives the token
+    pub auth: &'static str,
+    pub tags: Vec<String>,
+    pub group: Option<String>,
+    pub extends: Option<String>,
+    pub current: bool,
+}
+
+impl ProviderView {
+    pub fn new(provider: &Provider, current: bool, options: &OutputOptions) -> Self {
+        Self {
+            name: provider.name.clone(),
+            api_url: provider.api_url.clone(),
+            token: provider.has_token().then(|| options.token(&provider.token)),
+            model: provider.model.clone(),
+            opus_model: provider.opus_model.clone(),
+            sonnet_model: provider.sonnet_model.clone(),
+            haiku_model: provider.haiku_model.clone(),
+            auth: provider.auth_style().env_var(),
+            tags: provider.tags.clone(),
+            group: provider.group.clone(),
+            extends: provider.extends.clone(),
+            current,
+        }
+    }
+
+    /// Tab-separated line for `--format plain`
+    pub fn plain_line(&self) -> String {
+        let tags = self.tags.join(",");
+        [
+            self.name.as_str(),
+            self.api_url.as_str(),
+            self.model.as_deref().unwrap_or("-"),
+            self.token.as_deref().unwrap_or("-"),
+            if self.current { "*" } else { "" },
+            if tags.is_empty() { "-" } else { tags.as_str() },
+            self.group.as_deref().unwrap_or("-"),
+        ]
+        .join("\t")
+    }
+}
+
+/// Result of comparing the live environment with the configuration
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// Environment variables match the checked provider
+    Match,
+    /// Environment variables differ from the checked provider
+    Mismatch,
+    /// No provider is selected
+    NoProvider,
+    /// The selected provider is missing from the configuration
+    UnknownProvider,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Match => "match",
+            CheckStatus::Mismatch => "mismatch",
+            CheckStatus::NoProvider => "no_provider",
+            CheckStatus::UnknownProvider => "unknown_provider",
+        }
+    }
+}
+
+/// Report produced by `check`
+#[derive(Serialize)]
+pub struct CheckReport {
+    /// Variable the checked provider passes its token in, see `AuthStyle`
+    pub auth_var: &'static str,
+    pub auth_token: Option<String>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    /// Provider exported into this shell through `CCE_PROVIDER`
+    pub shell_provider: Option<String>,
+    /// Persisted default that new shells load
+    pub current_provider: Option<String>,
+    /// Whether this shell runs something other than the persisted default
+    pub default_differs: bool,
+    pub configured_url: Option<String>,
+    pub status: CheckStatus,
+}
+
+impl CheckReport {
+    /// Provider the environment was compared against
+    pub fn checked_provider(&self) -> Option<&str> {
+        self.shell_provider
+            .as_deref()
+            .or(self.current_provider.as_deref())
+    }
+
+    /// `key=value` lines for `--format plain`
+    pub fn plain_lines(&self) -> Vec<String> {
+        let or_empty = |value: &Option<String>| value.clone().unwrap_or_default();
+        vec![
+            format!("auth_var={}", self.auth_var),
+            format!("auth_token={}", or_empty(&self.auth_token)),
+            format!("base_url={}", or_empty(&self.base_url)),
+            format!("model={}", or_empty(&self.model)),
+            format!("shell_provider={}", or_empty(&self.shell_provider)),
+            format!("current_provider={}", or_empty(&self.current_provider)),
+            format!("default_differs={}", self.default_differs),
+            format!("configured_url={}", or_empty(&self.configured_url)),
+            format!("status={}", self.status.as_str()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masking_keeps_eight_characters() {
+        assert_eq!(mask_token("sk-ant-api03-secret"), "sk-ant-a****");
+        assert_eq!(mask_token("short"), "****");
+        // Multi-byte characters are counted, not split
+        assert_eq!(mask_token("密钥密钥密钥密钥密钥"), "密钥密钥密钥密钥****");
+    }
+}
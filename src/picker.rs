@@ -0,0 +1,161 @@
+use crate::fuzzy;
+use crate::theme;
+use anyhow::{bail, Result};
+use crossterm::{
+    cursor::{MoveToColumn, MoveToPreviousLine},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Print, Stylize},
+    terminal::{self, Clear, ClearType},
+};
+use std::io::Write;
+
+/// Rows of matches shown below the query line
+const VISIBLE_ROWS: usize = 8;
+
+/// Entry offered by the picker
+pub struct PickerItem {
+    pub name: String,
+    /// Dimmed text shown next to the name, e.g. the API URL
+    pub detail: String,
+}
+
+/// Let the user fuzzy-search `items` in a few lines below the cursor.
+///
+/// The picker draws on the terminal itself rather than stdout, so it also works while the
+/// shell wrapper captures stdout. Returns `None` when cancelled with Esc or Ctrl-C.
+pub fn pick(prompt: &str, items: &[PickerItem]) -> Result<Option<String>> {
+    let Some(mut out) = open_terminal() else {
+        bail!("No terminal available to pick from, pass a name instead");
+    };
+
+    terminal::enable_raw_mode()?;
+    let result = run(&mut out, prompt, items);
+    // Leave no trace of the picker behind, whatever happened
+    let _ = queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown));
+    let _ = out.flush();
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn run(out: &mut impl Write, prompt: &str, items: &[PickerItem]) -> Result<Option<String>> {
+    let mut query = String::new();
+    let mut selected = 0;
+
+    loop {
+        let matches = fuzzy::filter(&query, items, |item| item.name.as_str());
+        selected = selected.min(matches.len().saturating_sub(1));
+        draw(out, prompt, &query, items, &matches, selected)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Enter => {
+                return Ok(matches
+                    .get(selected)
+                    .map(|(index, _)| items[*index].name.clone()))
+            }
+            KeyCode::Down | KeyCode::Tab if !matches.is_empty() => {
+                selected = (selected + 1) % matches.len();
+            }
+            KeyCode::Up | KeyCode::BackTab => {
+                selected = selected
+                    .checked_sub(1)
+                    .unwrap_or(matches.len().saturating_sub(1));
+            }
+            KeyCode::Backspace => {
+                query.pop();
+                selected = 0;
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                selected = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw(
+    out: &mut impl Write,
+    prompt: &str,
+    query: &str,
+    items: &[PickerItem],
+    matches: &[(usize, fuzzy::FuzzyMatch)],
+    selected: usize,
+) -> Result<()> {
+    queue!(
+        out,
+        MoveToColumn(0),
+        Clear(ClearType::FromCursorDown),
+        Print(format!(
+            "{} {}",
+            prompt.with(theme::current().accent.into()).bold(),
+            query
+        ))
+    )?;
+
+    // Scroll the window so the selection stays visible
+    let start = selected.saturating_sub(VISIBLE_ROWS - 1);
+    let mut rows = 0;
+    for (row, (index, matched)) in matches.iter().enumerate().skip(start).take(VISIBLE_ROWS) {
+        let item = &items[*index];
+        let is_selected = row == selected;
+        queue!(
+            out,
+            Print("\r\n"),
+            Print(if is_selected { "> " } else { "  " })
+        )?;
+        // Highlight the characters the query matched
+        for (position, c) in item.name.chars().enumerate() {
+            let styled = if matched.positions.contains(&position) {
+                c.with(theme::current().warning.into()).bold()
+            } else if is_selected {
+                c.with(theme::current().success.into()).bold()
+            } else {
+                c.reset()
+            };
+            queue!(out, Print(styled))?;
+        }
+        queue!(out, Print("  "), Print(item.detail.as_str().dark_grey()))?;
+        rows += 1;
+    }
+    if matches.is_empty() {
+        queue!(out, Print("\r\n"), Print("  no matches".dark_grey()))?;
+        rows += 1;
+    }
+
+    // Return to the query line so the cursor sits after what was typed
+    queue!(
+        out,
+        MoveToPreviousLine(rows),
+        MoveToColumn((prompt.chars().count() + 1 + query.chars().count()) as u16)
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+/// The controlling terminal, even when stdout and stderr are redirected
+fn open_terminal() -> Option<Box<dyn Write>> {
+    #[cfg(unix)]
+    {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .ok()
+            .map(|tty| Box::new(tty) as Box<dyn Write>)
+    }
+    #[cfg(not(unix))]
+    {
+        use std::io::IsTerminal;
+        std::io::stderr()
+            .is_terminal()
+            .then(|| Box::new(std::io::stderr()) as Box<dyn Write>)
+    }
+}
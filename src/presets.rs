@@ -0,0 +1,205 @@
+use crate::config::{AuthStyle, Provider};
+use serde::Serialize;
+
+/// Known gateway with its endpoint, auth variable and recommended models
+#[derive(Serialize)]
+pub struct Preset {
+    pub id: &'static str,
+    /// Alternative ids accepted by `--preset`
+    pub aliases: &'static [&'static str],
+    pub title: &'static str,
+    pub api_url: &'static str,
+    pub auth: AuthStyle,
+    pub model: Option<&'static str>,
+    pub opus_model: Option<&'static str>,
+    pub sonnet_model: Option<&'static str>,
+    pub haiku_model: Option<&'static str>,
+    /// Placeholder token for gateways that ignore authentication
+    pub default_token: Option<&'static str>,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        id: "anthropic",
+        aliases: &["claude"],
+        title: "Anthropic API (direct)",
+        api_url: "https://api.anthropic.com",
+        auth: AuthStyle::ApiKey,
+        model: None,
+        opus_model: None,
+        sonnet_model: None,
+        haiku_model: None,
+        default_token: None,
+    },
+    Preset {
+        id: "bedrock-proxy",
+        aliases: &["bedrock"],
+        title: "Bedrock-style proxy (local gateway in front of AWS Bedrock)",
+        api_url: "http://localhost:8080",
+        auth: AuthStyle::AuthToken,
+        model: None,
+        opus_model: Some("us.anthropic.claude-opus-4-1-20250805-v1:0"),
+        sonnet_model: Some("us.anthropic.claude-sonnet-4-5-20250929-v1:0"),
+        haiku_model: Some("us.anthropic.claude-3-5-haiku-20241022-v1:0"),
+        default_token: None,
+    },
+    Preset {
+        id: "openrouter",
+        aliases: &[],
+        title: "OpenRouter",
+        api_url: "https://openrouter.ai/api",
+        auth: AuthStyle::AuthToken,
+        model: None,
+        opus_model: Some("anthropic/claude-opus-4.1"),
+        sonnet_model: Some("anthropic/claude-sonnet-4.5"),
+        haiku_model: Some("anthropic/claude-3.5-haiku"),
+        default_token: None,
+    },
+    Preset {
+        id: "deepseek",
+        aliases: &[],
+        title: "DeepSeek",
+        api_url: "https://api.deepseek.com/anthropic",
+        auth: AuthStyle::AuthToken,
+        model: Some("deepseek-chat"),
+        opus_model: None,
+        sonnet_model: None,
+        haiku_model: None,
+        default_token: None,
+    },
+    Preset {
+        id: "kimi",
+        aliases: &["moonshot"],
+        title: "Moonshot AI / Kimi",
+        api_url: "https://api.moonshot.cn/anthropic",
+        auth: AuthStyle::AuthToken,
+        model: Some("kimi-k2-turbo-preview"),
+        opus_model: None,
+        sonnet_model: None,
+        haiku_model: None,
+        default_token: None,
+    },
+    Preset {
+        id: "glm",
+        aliases: &["zhipu", "bigmodel"],
+        title: "Zhipu GLM",
+        api_url: "https://open.bigmodel.cn/api/anthropic",
+        auth: AuthStyle::AuthToken,
+        model: None,
+        opus_model: Some("glm-4.6"),
+        sonnet_model: Some("glm-4.6"),
+        haiku_model: Some("glm-4.5-air"),
+        default_token: None,
+    },
+    Preset {
+        id: "qwen",
+        aliases: &["dashscope"],
+        title: "Alibaba Qwen (DashScope)",
+        api_url: "https://dashscope.aliyuncs.com/apps/anthropic",
+        auth: AuthStyle::AuthToken,
+        model: None,
+        opus_model: Some("qwen3-coder-plus"),
+        sonnet_model: Some("qwen3-coder-plus"),
+        haiku_model: Some("qwen3-coder-flash"),
+        default_token: None,
+    },
+    Preset {
+        id: "ollama",
+        aliases: &[],
+        title: "Ollama (local)",
+        api_url: "http://localhost:11434",
+        auth: AuthStyle::AuthToken,
+        model: None,
+        opus_model: None,
+        sonnet_model: None,
+        haiku_model: None,
+        default_token: Some("ollama"),
+    },
+    Preset {
+        id: "litellm",
+        aliases: &[],
+        title: "LiteLLM proxy (local)",
+        api_url: "http://localhost:4000",
+        auth: AuthStyle::AuthToken,
+        model: None,
+        opus_model: None,
+        sonnet_model: None,
+        haiku_model: None,
+        default_token: None,
+    },
+];
+
+/// Look up a preset by id or alias, ignoring case
+pub fn find_preset(id: &str) -> Option<&'static Preset> {
+    let id = id.to_ascii_lowercase();
+    PRESETS
+        .iter()
+        .find(|preset| preset.id == id || preset.aliases.contains(&id.as_str()))
+}
+
+impl Preset {
+    /// Fill the fields left unset on `provider` with the preset's values
+    pub fn apply(&self, provider: &mut Provider) {
+        if provider.api_url.is_empty() {
+            provider.api_url = self.api_url.to_string();
+        }
+        if !provider.has_token() {
+            if let Some(token) = self.default_token {
+                provider.token = token.to_string();
+            }
+        }
+
+        let fill = |field: &mut Option<String>, value: Option<&str>| {
+            if field.is_none() {
+                *field = value.map(str::to_string);
+            }
+        };
+        // An explicitly chosen model applies to every tier, so skip the recommendations
+        if provider.model.is_none() {
+            fill(&mut provider.opus_model, self.opus_model);
+            fill(&mut provider.sonnet_model, self.sonnet_model);
+            fill(&mut provider.haiku_model, self.haiku_model);
+        }
+        fill(&mut provider.model, self.model);
+
+        if provider.auth.is_none() && self.auth != AuthStyle::default() {
+            provider.auth = Some(self.auth);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_presets_by_id_or_alias() {
+        assert_eq!(find_preset("Anthropic").unwrap().id, "anthropic");
+        assert_eq!(find_preset("claude").unwrap().id, "anthropic");
+        assert!(find_preset("nope").is_none());
+    }
+
+    #[test]
+    fn fills_only_what_is_unset() {
+        let preset = PRESETS
+            .iter()
+            .find(|preset| preset.opus_model.is_some())
+            .unwrap();
+
+        let mut provider = Provider::default();
+        preset.apply(&mut provider);
+        assert_eq!(provider.api_url, preset.api_url);
+        assert_eq!(provider.opus_model.as_deref(), preset.opus_model);
+
+        let mut provider = Provider {
+            api_url: "https://mirror.example.com".to_string(),
+            model: Some("my-model".to_string()),
+            ..Default::default()
+        };
+        preset.apply(&mut provider);
+        assert_eq!(provider.api_url, "https://mirror.example.com");
+        // A chosen model applies to every tier
+        assert_eq!(provider.model.as_deref(), Some("my-model"));
+        assert_eq!(provider.opus_model, None);
+    }
+}
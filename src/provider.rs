@@ -1,132 +1,665 @@
-use crate::config::{Config, Provider};
+use crate::cli::{ConflictPolicy, OutputFormat};
+use crate::completions;
+use crate::config::{validate_api_url, AuthStyle, Bundle, Config, Provider};
 use crate::constants::*;
-use anyhow::Result;
+use crate::error::CceError;
+use crate::fuzzy;
+use crate::history::{self, HistoryEntry, Scope};
+use crate::models;
+use crate::output::{CheckReport, CheckStatus, OutputOptions, ProviderView};
+use crate::picker::{self, PickerItem};
+use crate::presets::{find_preset, PRESETS};
+use crate::rules;
+use crate::theme::{self, Paint};
+use crate::trash;
+use anyhow::{bail, Context, Result};
 use colored::*;
+use serde::Serialize;
+use std::io::{IsTerminal, Read, Write};
+use std::path::Path;
 
 pub struct ProviderManager;
 
 impl ProviderManager {
-    pub fn list_providers(config: &Config) -> Result<()> {
-        if config.providers.is_empty() {
-            println!("{}", "No service providers configured".yellow());
+    pub fn list_providers(
+        config: &Config,
+        tags: &[String],
+        group: Option<&str>,
+        options: &OutputOptions,
+    ) -> Result<()> {
+        let mut providers: Vec<&Provider> = config
+            .providers
+            .values()
+            .filter(|provider| tags.iter().all(|tag| provider.has_tag(tag)))
+            .filter(|provider| group.is_none() || provider.group.as_deref() == group)
+            .collect();
+
+        if options.format != OutputFormat::Text {
+            providers.sort_by(|a, b| a.name.cmp(&b.name));
+            let views: Vec<ProviderView> = providers
+                .into_iter()
+                .map(|provider| {
+                    let is_current = config.current_provider.as_ref() == Some(&provider.name);
+                    let resolved = config
+                        .resolve_provider(&provider.name)
+                        .unwrap_or_else(|_| provider.clone());
+                    ProviderView::new(&resolved, is_current, options)
+                })
+                .collect();
+
+            if options.is_structured() {
+                return options.emit(&views);
+            }
+            for view in views {
+                println!("{}", view.plain_line());
+            }
             return Ok(());
         }
 
-        println!("{}", "Configured service providers:".blue().bold());
+        if providers.is_empty() {
+            if config.providers.is_empty() {
+                println!("{}", "No service providers configured".warning());
+            } else {
+                println!("{}", "No service providers match the filter".warning());
+            }
+            return Ok(());
+        }
+
+        println!("{}", "Configured service providers:".info().bold());
         println!();
 
-        for (name, provider) in &config.providers {
-            let is_current = config.current_provider.as_ref() == Some(name);
+        for provider in providers {
+            Self::print_provider(config, provider, options);
+            println!();
+        }
 
-            let marker = if is_current {
-                "●".green()
-            } else {
-                "○".white()
-            };
-            let name_color = if is_current {
-                name.green().bold()
-            } else {
-                name.white()
-            };
+        Ok(())
+    }
+
+    /// Print one provider in the `list` layout, with inherited fields resolved
+    fn print_provider(config: &Config, provider: &Provider, options: &OutputOptions) {
+        let name = &provider.name;
+        let is_current = config.current_provider.as_ref() == Some(name);
+        let resolved = config.resolve_provider(name);
+        let shown = resolved.as_ref().unwrap_or(provider);
+
+        let marker = if is_current {
+            theme::ACTIVE.as_str().success()
+        } else {
+            theme::INACTIVE.as_str().plain()
+        };
+        let name_color = if is_current {
+            name.success().bold()
+        } else {
+            name.plain()
+        };
 
-            println!("  {} {}", marker, name_color);
-            println!("    API URL: {}", provider.api_url.cyan());
+        println!("  {} {}", marker, name_color);
+        if let Some(ref base) = provider.extends {
+            println!("    Extends: {}", base.info());
+        }
+        println!("    API URL: {}", shown.api_url.accent());
+        if shown.has_token() {
+            println!("    Token: {}", options.token(&shown.token).muted());
+        } else {
+            println!("    Token: {}", "(missing)".warning());
+        }
+        if let Some(ref model) = shown.model {
+            println!("    Model: {}", model.accent());
+        }
+        for (tier, model) in [
+            ("Opus", &shown.opus_model),
+            ("Sonnet", &shown.sonnet_model),
+            ("Haiku", &shown.haiku_model),
+        ] {
+            if let Some(model) = model {
+                println!("    {} model: {}", tier, model.accent());
+            }
+        }
+        if shown.auth_style() != AuthStyle::default() {
+            println!("    Auth: {}", shown.auth_style().env_var().accent());
+        }
+        if !provider.tags.is_empty() {
+            println!("    Tags: {}", provider.tags.join(", ").info());
+        }
+        if let Some(ref group) = provider.group {
+            let is_default = config
+                .group_default(group)
+                .is_some_and(|default| &default.name == name);
             println!(
-                "    Token: {}****",
-                &provider.token[..provider.token.len().min(8)].dimmed()
+                "    Group: {}{}",
+                group.info(),
+                if is_default { " (default)" } else { "" }
             );
-            if let Some(ref model) = provider.model {
-                println!("    Model: {}", model.cyan());
+        }
+        if let Err(err) = &resolved {
+            println!("    {}", format!("{} {}", theme::ERROR, err).error());
+        }
+
+        if is_current {
+            println!("    {}", "(currently active)".success().italic());
+        }
+    }
+
+    pub fn list_presets(options: &OutputOptions) -> Result<()> {
+        match options.format {
+            OutputFormat::Json | OutputFormat::Yaml => return options.emit(&PRESETS),
+            OutputFormat::Plain => {
+                for preset in PRESETS {
+                    println!("{}\t{}\t{}", preset.id, preset.api_url, preset.title);
+                }
+                return Ok(());
             }
+            OutputFormat::Text => {}
+        }
 
-            if is_current {
-                println!("    {}", "(currently active)".green().italic());
+        println!("{}", "Available gateway presets:".info().bold());
+        println!();
+        for preset in PRESETS {
+            println!("  {} {}", preset.id.success().bold(), preset.title.muted());
+            println!("    API URL: {}", preset.api_url.accent());
+            println!("    Auth: {}", preset.auth.env_var().accent());
+            let models: Vec<String> = [
+                ("model", preset.model),
+                ("opus", preset.opus_model),
+                ("sonnet", preset.sonnet_model),
+                ("haiku", preset.haiku_model),
+            ]
+            .into_iter()
+            .filter_map(|(tier, model)| model.map(|model| format!("{}={}", tier, model)))
+            .collect();
+            if !models.is_empty() {
+                println!("    Models: {}", models.join(", ").accent());
             }
             println!();
         }
+        println!(
+            "{} Use {} to add one",
+            theme::HINT.as_str().info(),
+            "cce add <name> --preset <id>".warning()
+        );
+        Ok(())
+    }
+
+    pub fn list_models(
+        config: &Config,
+        name: &str,
+        offline: bool,
+        options: &OutputOptions,
+    ) -> Result<()> {
+        let provider = config.resolve_provider(name)?;
+        if !offline && !provider.has_token() {
+            bail!(
+                "Service provider '{}' has no token, run 'cce use {}' to set it",
+                name,
+                name
+            );
+        }
+
+        let list = models::load_models(&provider, offline)?;
+
+        #[derive(Serialize)]
+        struct ModelsView<'a> {
+            provider: &'a str,
+            models: &'a [String],
+            fetched_at: u64,
+            cached: bool,
+        }
+
+        match options.format {
+            OutputFormat::Json | OutputFormat::Yaml => {
+                return options.emit(&ModelsView {
+                    provider: name,
+                    models: &list.models,
+                    fetched_at: list.fetched_at,
+                    cached: list.stale_reason.is_some(),
+                })
+            }
+            OutputFormat::Plain => {
+                for model in &list.models {
+                    println!("{}", model);
+                }
+                return Ok(());
+            }
+            OutputFormat::Text => {}
+        }
+
+        if let Some(reason) = &list.stale_reason {
+            println!(
+                "{} Could not refresh the list ({}), showing models cached {}",
+                theme::WARNING.as_str().warning(),
+                reason,
+                Self::format_age(list.fetched_at)
+            );
+            println!();
+        }
+
+        if list.models.is_empty() {
+            println!("{}", "The gateway did not report any models".warning());
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            format!("Models available on '{}':", name).info().bold()
+        );
+        for model in &list.models {
+            if provider.model.as_ref() == Some(model) {
+                println!(
+                    "  {} {}",
+                    theme::ACTIVE.as_str().success(),
+                    model.success().bold()
+                );
+            } else {
+                println!("  {} {}", theme::INACTIVE.as_str().plain(), model);
+            }
+        }
 
         Ok(())
     }
 
+    /// Describe a Unix timestamp relative to now, e.g. "5 minutes ago"
+    fn format_age(timestamp: u64) -> String {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let age = now.saturating_sub(timestamp);
+        match age {
+            0..=59 => "just now".to_string(),
+            60..=3599 => format!("{} minutes ago", age / 60),
+            3600..=86399 => format!("{} hours ago", age / 3600),
+            _ => format!("{} days ago", age / 86400),
+        }
+    }
+
+    /// Show a single provider, as stored or with inheritance applied
+    pub fn show_provider(
+        config: &Config,
+        name: &str,
+        resolved: bool,
+        options: &OutputOptions,
+    ) -> Result<()> {
+        let Some(stored) = config.providers.get(name) else {
+            return Err(config.provider_not_found(name).into());
+        };
+        let provider = if resolved {
+            config.resolve_provider(name)?
+        } else {
+            stored.clone()
+        };
+        let is_current = config.current_provider.as_deref() == Some(name);
+        let view = ProviderView::new(&provider, is_current, options);
+
+        match options.format {
+            OutputFormat::Json | OutputFormat::Yaml => options.emit(&view),
+            OutputFormat::Plain => {
+                println!("{}", view.plain_line());
+                Ok(())
+            }
+            OutputFormat::Text if resolved => {
+                Self::print_provider(config, stored, options);
+                Ok(())
+            }
+            OutputFormat::Text => {
+                let unset = || {
+                    let label = if provider.extends.is_some() {
+                        "(inherited)"
+                    } else {
+                        "(not set)"
+                    };
+                    label.muted().to_string()
+                };
+                println!("  {}", name.bold());
+                if let Some(ref base) = provider.extends {
+                    println!("    Extends: {}", base.info());
+                }
+                println!(
+                    "    API URL: {}",
+                    if provider.api_url.is_empty() {
+                        unset()
+                    } else {
+                        provider.api_url.accent().to_string()
+                    }
+                );
+                println!(
+                    "    Token: {}",
+                    if provider.has_token() {
+                        options.token(&provider.token).muted().to_string()
+                    } else {
+                        unset()
+                    }
+                );
+                println!(
+                    "    Model: {}",
+                    match provider.model {
+                        Some(ref model) => model.accent().to_string(),
+                        None => unset(),
+                    }
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Add `provider`, asking for its token when it has none
     pub fn add_provider(
         config: &mut Config,
-        name: String,
-        api_url: String,
-        token: String,
-        model: Option<String>,
+        mut provider: Provider,
+        preset: Option<&str>,
+        token_stdin: bool,
+        force: bool,
     ) -> Result<()> {
+        let name = provider.name.trim().to_string();
+        if name.is_empty() {
+            bail!("Provider name must not be empty");
+        }
+
+        if let Some(id) = preset {
+            let Some(preset) = find_preset(id) else {
+                bail!(
+                    "Unknown preset '{}', run 'cce presets' to see the catalog",
+                    id
+                );
+            };
+            provider.api_url = provider.api_url.trim().to_string();
+            preset.apply(&mut provider);
+        }
+
+        let api_url = provider.api_url.trim().to_string();
+        let extends = provider
+            .extends
+            .take()
+            .map(|base| base.trim().to_string())
+            .filter(|base| !base.is_empty());
+        if let Some(base) = &extends {
+            if !config.providers.contains_key(base) {
+                return Err(CceError::NotFound(format!(
+                    "Base service provider '{}' does not exist",
+                    base
+                ))
+                .into());
+            }
+        }
+        if !api_url.is_empty() {
+            validate_api_url(&api_url)?;
+        } else if extends.is_none() {
+            bail!("An API URL is required unless the provider uses --extends");
+        }
+
+        // Refuse before asking for a token that would be thrown away
         if config.providers.contains_key(&name) {
+            if !force {
+                bail!(
+                    "Service provider '{}' already exists, use --force to overwrite it",
+                    name
+                );
+            }
             println!(
                 "{} Service provider '{}' already exists, overwriting",
-                "⚠️".yellow(),
-                name.yellow()
+                theme::WARNING.as_str().warning(),
+                name.warning()
             );
         }
 
-        config.add_provider(name.clone(), api_url, token, model);
+        // A provider extending one with a token can leave its own unset
+        let inherits_token = extends.as_ref().is_some_and(|base| {
+            config
+                .resolve_provider(base)
+                .is_ok_and(|base| base.has_token())
+        });
+
+        let token = if provider.has_token() {
+            provider.token
+        } else if token_stdin {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .with_context(|| "Failed to read token from stdin")?;
+            input
+        } else if inherits_token {
+            String::new()
+        } else {
+            Self::prompt_secret(&format!("Token for '{}': ", name))?
+        };
+        let token = token.trim().to_string();
+        if token.is_empty() && !inherits_token {
+            bail!("Token must not be empty");
+        }
+
+        provider.model = provider
+            .model
+            .map(|model| model.trim().to_string())
+            .filter(|model| !model.is_empty());
+        provider.tags = Self::normalize_tags(&provider.tags);
+        provider.group = provider
+            .group
+            .map(|group| group.trim().to_string())
+            .filter(|group| !group.is_empty());
+
+        config.providers.insert(
+            name.clone(),
+            Provider {
+                name: name.clone(),
+                api_url,
+                token,
+                extends,
+                ..provider
+            },
+        );
+        // Overwriting an existing base with --force could close a loop
+        config.resolve_provider(&name)?;
         config.save()?;
 
         println!(
             "{} Successfully added service provider '{}'",
-            "✅".green(),
-            name.green().bold()
+            theme::SUCCESS.as_str().success(),
+            name.success().bold()
         );
         Ok(())
     }
 
-    pub fn remove_provider(config: &mut Config, name: &str) -> Result<()> {
+    pub fn tag_provider(
+        config: &mut Config,
+        name: &str,
+        tags: &[String],
+        remove: bool,
+    ) -> Result<()> {
+        let Some(provider) = config.providers.get_mut(name) else {
+            return Err(config.provider_not_found(name).into());
+        };
+
+        let tags = Self::normalize_tags(tags);
+        if remove {
+            provider.tags.retain(|tag| !tags.contains(tag));
+        } else {
+            provider.tags.extend(tags);
+            provider.tags = Self::normalize_tags(&provider.tags);
+        }
+        let summary = if provider.tags.is_empty() {
+            "(none)".to_string()
+        } else {
+            provider.tags.join(", ")
+        };
+        config.save()?;
+
+        println!(
+            "{} Tags for '{}': {}",
+            theme::TAG.as_str().success(),
+            name.success().bold(),
+            summary.accent()
+        );
+        Ok(())
+    }
+
+    pub fn set_provider_group(
+        config: &mut Config,
+        name: &str,
+        group: Option<String>,
+        make_default: bool,
+    ) -> Result<()> {
+        let Some(provider) = config.providers.get_mut(name) else {
+            return Err(config.provider_not_found(name).into());
+        };
+
+        let group = group
+            .map(|group| group.trim().to_string())
+            .filter(|group| !group.is_empty());
+        let previous = std::mem::replace(&mut provider.group, group.clone());
+
+        // A provider leaving a group can no longer be its default
+        if let Some(previous) = previous.filter(|previous| Some(previous) != group.as_ref()) {
+            if let Some(settings) = config.groups.get_mut(&previous) {
+                if settings.default.as_deref() == Some(name) {
+                    settings.default = None;
+                }
+            }
+        }
+
+        if let (Some(group), true) = (&group, make_default) {
+            config.groups.entry(group.clone()).or_default().default = Some(name.to_string());
+        }
+        // Group settings only carry a default, so drop the ones left empty
+        config
+            .groups
+            .retain(|_, settings| settings.default.is_some());
+        config.save()?;
+
+        match &group {
+            Some(group) => println!(
+                "{} Moved '{}' into group '{}'{}",
+                theme::GROUP.as_str().success(),
+                name.success().bold(),
+                group.accent(),
+                if make_default { " as its default" } else { "" }
+            ),
+            None => println!(
+                "{} Removed '{}' from its group",
+                theme::GROUP.as_str().success(),
+                name.success().bold()
+            ),
+        }
+        Ok(())
+    }
+
+    /// Trim, drop empty entries, sort and deduplicate tags
+    fn normalize_tags(tags: &[String]) -> Vec<String> {
+        let mut tags: Vec<String> = tags
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    pub fn remove_provider(config: &mut Config, name: &str, force: bool) -> Result<()> {
         if !config.providers.contains_key(name) {
-            println!(
-                "{} Service provider '{}' does not exist",
-                "❌".red(),
-                name.red()
+            return Err(config.provider_not_found(name).into());
+        }
+        let dependents = config.dependents(name);
+        if !dependents.is_empty() && !force {
+            bail!(
+                "Service provider '{}' is extended by {}, delete those first or use --force",
+                name,
+                dependents.join(", ")
             );
-            return Ok(());
         }
 
+        trash::put(config.providers[name].clone())?;
         config.remove_provider(name);
         config.save()?;
 
         println!(
             "{} Successfully removed service provider '{}'",
-            "🗑️".green(),
-            name.green().bold()
+            theme::DELETED.as_str().success(),
+            name.success().bold()
         );
+        println!(
+            "{} Run 'cce restore {}' to bring it back",
+            theme::HINT.as_str().info(),
+            name
+        );
+
+        if !dependents.is_empty() {
+            println!(
+                "{} These providers extend '{}' and can no longer be resolved: {}",
+                theme::WARNING.as_str().warning(),
+                name,
+                dependents.join(", ").warning()
+            );
+        }
         Ok(())
     }
 
-    pub fn use_provider(config: &mut Config, name: &str) -> Result<()> {
-        if !config.providers.contains_key(name) {
-            println!(
-                "{} Service provider '{}' does not exist",
-                "❌".red(),
-                name.red()
+    pub fn restore_provider(config: &mut Config, name: &str) -> Result<()> {
+        if config.providers.contains_key(name) {
+            bail!(
+                "Service provider '{}' already exists, delete it before restoring the old one",
+                name
             );
-            return Ok(());
         }
+        let Some(provider) = trash::find(name)? else {
+            let deleted = trash::names()?;
+            let suggestions = fuzzy::suggestions(name, deleted.iter().map(String::as_str));
+            let mut message = format!("No deleted service provider named '{}' in the trash", name);
+            if !suggestions.is_empty() {
+                message.push_str(&format!(". Did you mean: {}?", suggestions.join(", ")));
+            }
+            return Err(CceError::NotFound(message).into());
+        };
+
+        config.providers.insert(name.to_string(), provider);
+        config.save()?;
+        // Only forget the deletion once the provider is safely back in the config
+        trash::remove(name)?;
+
+        println!(
+            "{} Restored service provider '{}'",
+            theme::RESTORED.as_str().success(),
+            name.success().bold()
+        );
+        Ok(())
+    }
+
+    pub fn use_provider(config: &mut Config, name: &str) -> Result<()> {
+        let name = config.match_provider_name(name)?;
+        let name = name.as_str();
 
         let shell_mode = Self::shell_integration_active();
 
+        // Providers imported from a redacted bundle ask for their token on first use
+        // The prompt goes to the terminal, so this works under the shell wrapper too
+        if !config.resolve_provider(name)?.has_token() {
+            let token = Self::prompt_secret(&format!("Enter token for '{}': ", name))?;
+            if token.is_empty() {
+                bail!("No token entered for service provider '{}'", name);
+            }
+            if let Some(provider) = config.providers.get_mut(name) {
+                provider.token = token;
+            }
+            config.save()?;
+        }
+
         if let Some(current) = &config.current_provider {
             if current == name && !shell_mode {
                 println!(
                     "{} Already using service provider '{}'",
-                    "ℹ️".blue(),
-                    name.blue().bold()
+                    theme::INFO.as_str().info(),
+                    name.info().bold()
                 );
                 return Ok(());
             }
         }
 
-        let provider = config
-            .providers
-            .get(name)
-            .expect("Provider should exist after check")
-            .clone();
+        let provider = config.resolve_provider(name)?;
 
         // Set environment variables
         config.set_current_provider(name);
         config.save()?;
+        // Saved as the default for new shells too, so the switch is global in either mode
+        Self::record_switch(name, Scope::Global);
 
         set_provider_env_vars(&provider);
 
@@ -135,123 +668,483 @@ impl ProviderManager {
         } else {
             println!(
                 "{} Switched to service provider '{}'",
-                "🔄".green(),
-                name.green().bold()
+                theme::SWITCH.as_str().success(),
+                name.success().bold()
             );
-            println!("  API URL: {}", provider.api_url.cyan());
+            println!("  API URL: {}", provider.api_url.accent());
         }
 
         Ok(())
     }
 
-    pub fn check_environment(config: &Config) -> Result<()> {
-        println!(
-            "{}",
-            "🔍 Checking environment variable status".blue().bold()
-        );
-        println!();
+    /// Point the active provider at another model.
+    ///
+    /// Without `save` only the current shell changes, which needs the shell integration.
+    pub fn switch_model(config: &mut Config, model: &str, save: bool) -> Result<()> {
+        let model = model.trim();
+        if model.is_empty() {
+            bail!("Model cannot be empty");
+        }
 
-        // Check current environment variables
-        let current_api_key = std::env::var(ENV_AUTH_TOKEN);
-        let current_api_url = std::env::var(ENV_BASE_URL);
+        // The shell's own provider wins over the persisted default
+        let shell_provider = std::env::var(ENV_PROVIDER)
+            .ok()
+            .filter(|name| !name.is_empty());
+        let Some(name) = shell_provider.or_else(|| config.current_provider.clone()) else {
+            return Err(CceError::NotFound(
+                "No service provider is active, run 'cce use <name>' first".to_string(),
+            )
+            .into());
+        };
+        if !config.providers.contains_key(&name) {
+            return Err(config.provider_not_found(&name).into());
+        }
 
-        println!("{}", "Current environment variables:".cyan().bold());
-        match &current_api_key {
-            Ok(key) => {
-                let masked_key = if key.len() > 8 {
-                    format!("{}****", &key[..8])
-                } else {
-                    "****".to_string()
-                };
-                println!("  {}: {}", ENV_AUTH_TOKEN, masked_key.green());
+        let shell_mode = Self::shell_integration_active();
+        if !shell_mode && !save {
+            bail!(
+                "Switching the model for this shell only needs the shell integration (see 'cce install'), or pass --save to store it on '{}'",
+                name
+            );
+        }
+
+        // Per-tier models, e.g. from a preset, would still win for their tier, so the new
+        // model replaces them as it does when given to `cce add`
+        if save {
+            if let Some(provider) = config.providers.get_mut(&name) {
+                provider.model = Some(model.to_string());
+                provider.clear_tier_models();
             }
-            Err(_) => {
-                println!("  {}: {}", ENV_AUTH_TOKEN, "Not set".red());
+            config.save()?;
+        }
+
+        let mut provider = config.resolve_provider(&name)?;
+        provider.model = Some(model.to_string());
+        provider.clear_tier_models();
+
+        if shell_mode {
+            println!("{}", generate_model_export_commands(&provider));
+        } else {
+            println!(
+                "{} Model for service provider '{}' set to '{}'",
+                theme::MODEL.as_str().success(),
+                name.success().bold(),
+                model.accent()
+            );
+            println!("  Run 'cce use {}' to apply it in this terminal", name);
+        }
+
+        Ok(())
+    }
+
+    /// Switch back to the provider used before the current one, like `cd -`
+    pub fn use_previous(config: &mut Config) -> Result<()> {
+        // This shell's provider counts as current, even if another shell changed the default
+        let current = std::env::var(ENV_PROVIDER)
+            .ok()
+            .filter(|name| !name.is_empty())
+            .or_else(|| config.current_provider.clone());
+
+        let entries = history::load();
+        let Some(previous) = history::recent_providers(&entries)
+            .into_iter()
+            .find(|name| Some(*name) != current.as_deref() && config.providers.contains_key(*name))
+            .map(str::to_string)
+        else {
+            return Err(CceError::NotFound(
+                "No previous service provider in the history".to_string(),
+            )
+            .into());
+        };
+
+        Self::use_provider(config, &previous)
+    }
+
+    /// Print the provider the rules select now, falling back to the default
+    pub fn show_auto_provider(config: &Config, options: &OutputOptions) -> Result<()> {
+        let Some(choice) = rules::active_provider(config)? else {
+            return Err(CceError::NotFound(
+                "No rule matches right now and no service provider is selected".to_string(),
+            )
+            .into());
+        };
+
+        if options.is_structured() {
+            return options.emit(&choice);
+        }
+        println!("{}", choice.provider);
+        Ok(())
+    }
+
+    /// Print the exports for the provider new shells load: the rule-selected one in auto mode,
+    /// else the default. Nothing is saved, so a rule's pick never replaces `current_provider`.
+    pub fn print_startup_exports(config: &Config) -> Result<()> {
+        let name = if config.auto_switch {
+            rules::active_provider(config)?.map(|choice| choice.provider)
+        } else {
+            config.current_provider.clone()
+        };
+        let Some(name) = name else {
+            return Ok(());
+        };
+
+        let provider = config.resolve_provider(&name)?;
+        if !provider.has_token() {
+            bail!(
+                "Token for service provider '{}' is missing, run 'cce use {}' to set it",
+                name,
+                name
+            );
+        }
+        Self::emit_export_commands(&provider);
+        Ok(())
+    }
+
+    /// Turn loading the rule-selected provider in new shells on or off
+    pub fn set_auto_switch(config: &mut Config, enabled: bool) -> Result<()> {
+        config.auto_switch = enabled;
+        config.save()?;
+
+        if enabled {
+            println!(
+                "{} Auto mode enabled: new shells load the provider chosen by the rules",
+                theme::AUTO.as_str().success()
+            );
+            if config.rules.is_empty() {
+                println!(
+                    "{} No [[rules]] are configured yet, so the default provider is still used",
+                    theme::WARNING.as_str().warning()
+                );
             }
+        } else {
+            println!(
+                "{} Auto mode disabled: new shells load the default provider",
+                theme::AUTO.as_str().success()
+            );
         }
+        Ok(())
+    }
 
-        match &current_api_url {
-            Ok(url) => {
-                println!("  {}: {}", ENV_BASE_URL, url.green());
+    /// List the most recent provider switches, newest first
+    pub fn show_history(limit: usize, options: &OutputOptions) -> Result<()> {
+        let entries = history::load();
+        let recent: Vec<&HistoryEntry> = entries.iter().rev().take(limit).collect();
+
+        match options.format {
+            OutputFormat::Json | OutputFormat::Yaml => return options.emit(&recent),
+            OutputFormat::Plain => {
+                for entry in &recent {
+                    println!(
+                        "{}\t{}\t{}",
+                        entry.timestamp,
+                        entry.provider,
+                        entry.scope.as_str()
+                    );
+                }
+                return Ok(());
             }
-            Err(_) => {
-                println!("  {}: {}", ENV_BASE_URL, "Not set".red());
+            OutputFormat::Text => {}
+        }
+
+        if recent.is_empty() {
+            println!("{}", "No provider switches recorded yet".warning());
+            return Ok(());
+        }
+
+        println!("{}", "Recent provider switches:".info().bold());
+        for entry in recent {
+            println!(
+                "  {:<16} {}  {}",
+                Self::format_age(entry.timestamp),
+                entry.provider.accent().bold(),
+                format!("({})", entry.scope.as_str()).muted()
+            );
+        }
+        Ok(())
+    }
+
+    /// Pick a provider with the inline fuzzy picker and switch to it
+    pub fn pick_provider(config: &mut Config) -> Result<()> {
+        if config.providers.is_empty() {
+            bail!("No service providers configured, add one with 'cce add'");
+        }
+
+        let mut items: Vec<PickerItem> = config
+            .providers
+            .values()
+            .map(|provider| PickerItem {
+                name: provider.name.clone(),
+                detail: config
+                    .resolve_provider(&provider.name)
+                    .map(|resolved| resolved.api_url)
+                    .unwrap_or_default(),
+            })
+            .collect();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+
+        match picker::pick("Use provider:", &items)? {
+            Some(name) => Self::use_provider(config, &name),
+            None => bail!("No service provider selected"),
+        }
+    }
+
+    /// Switch to the default member of `group`
+    pub fn use_group(config: &mut Config, group: &str) -> Result<()> {
+        let Some(provider) = config.group_default(group) else {
+            return Err(
+                CceError::NotFound(format!("Group '{}' has no service providers", group)).into(),
+            );
+        };
+        let name = provider.name.clone();
+        Self::use_provider(config, &name)
+    }
+
+    pub fn check_environment(config: &Config, options: &OutputOptions) -> Result<()> {
+        let report = Self::check_report(config, options);
+
+        match options.format {
+            OutputFormat::Json | OutputFormat::Yaml => options.emit(&report)?,
+            OutputFormat::Plain => {
+                for line in report.plain_lines() {
+                    println!("{}", line);
+                }
             }
+            OutputFormat::Text => Self::print_check_report(config, &report),
         }
 
+        // The report is already printed, the exit code lets scripts branch on it
+        let provider = report.checked_provider().unwrap_or_default();
+        match report.status {
+            CheckStatus::Match | CheckStatus::NoProvider => Ok(()),
+            CheckStatus::Mismatch => Err(CceError::EnvMismatch(format!(
+                "Environment does not match service provider '{}'",
+                provider
+            ))
+            .into()),
+            CheckStatus::UnknownProvider => Err(config.provider_not_found(provider).into()),
+        }
+    }
+
+    fn print_check_report(config: &Config, report: &CheckReport) {
+        println!(
+            "{}",
+            format!("{} Checking environment variable status", theme::CHECK)
+                .info()
+                .bold()
+        );
         println!();
 
-        // Check configuration status
-        if let Some(current_provider) = &config.current_provider {
-            if let Some(provider) = config.providers.get(current_provider) {
-                println!("{}", "CCE configuration status:".cyan().bold());
-                println!("  Current provider: {}", current_provider.green().bold());
-                println!("  Configured URL: {}", provider.api_url.cyan());
+        println!("{}", "Current environment variables:".accent().bold());
+        match &report.auth_token {
+            Some(key) => println!("  {}: {}", report.auth_var, key.success()),
+            None => println!("  {}: {}", report.auth_var, "Not set".error()),
+        }
+        match &report.base_url {
+            Some(url) => println!("  {}: {}", ENV_BASE_URL, url.success()),
+            None => println!("  {}: {}", ENV_BASE_URL, "Not set".error()),
+        }
 
-                // Verify if environment variables match configuration
-                let env_matches = match (&current_api_key, &current_api_url) {
-                    (Ok(env_key), Ok(env_url)) => {
-                        env_key == &provider.token && env_url == &provider.api_url
+        println!();
+
+        // Check configuration status
+        match report.status {
+            CheckStatus::Match | CheckStatus::Mismatch => {
+                let checked = report.checked_provider().unwrap_or_default();
+                println!("{}", "CCE configuration status:".accent().bold());
+                match &report.shell_provider {
+                    Some(shell_provider) => {
+                        println!(
+                            "  Shell provider: {} (from {})",
+                            shell_provider.success().bold(),
+                            ENV_PROVIDER
+                        );
+                        println!(
+                            "  Default provider: {}",
+                            report
+                                .current_provider
+                                .as_deref()
+                                .unwrap_or("None selected")
+                                .accent()
+                        );
                     }
-                    _ => false,
-                };
+                    None => println!("  Current provider: {}", checked.success().bold()),
+                }
+                println!(
+                    "  Configured URL: {}",
+                    report
+                        .configured_url
+                        .as_deref()
+                        .unwrap_or_default()
+                        .accent()
+                );
 
-                if env_matches {
+                if report.status == CheckStatus::Match {
                     println!(
                         "  Status: {}",
-                        "✅ Environment variables match configuration".green()
+                        format!(
+                            "{} Environment variables match configuration",
+                            theme::SUCCESS
+                        )
+                        .success()
                     );
                 } else {
                     println!(
                         "  Status: {}",
-                        "⚠️ Environment variables do not match configuration".yellow()
+                        format!(
+                            "{} Environment variables do not match configuration",
+                            theme::WARNING
+                        )
+                        .warning()
                     );
+                    println!("  Suggestion: Run 'cce use {}' to reset", checked.accent());
+                }
+
+                if report.default_differs {
+                    match &report.current_provider {
+                        Some(default) => println!(
+                            "  {} This shell runs '{}' while new shells load '{}'",
+                            theme::INFO.as_str().info(),
+                            checked.accent(),
+                            default.accent()
+                        ),
+                        None => println!(
+                            "  {} This shell runs '{}' while new shells load no provider",
+                            theme::INFO.as_str().info(),
+                            checked.accent()
+                        ),
+                    }
+                }
+            }
+            CheckStatus::UnknownProvider => {
+                if let Some(shell_provider) = &report.shell_provider {
                     println!(
-                        "  Suggestion: Run 'cce use {}' to reset",
-                        current_provider.cyan()
+                        "{}",
+                        format!(
+                            "{} Configuration error: Shell provider '{}' does not exist",
+                            theme::ERROR,
+                            shell_provider
+                        )
+                        .error()
+                    );
+                } else {
+                    println!(
+                        "{}",
+                        format!(
+                            "{} Configuration error: Current provider does not exist",
+                            theme::ERROR
+                        )
+                        .error()
                     );
                 }
-            } else {
-                println!(
-                    "{}",
-                    "❌ Configuration error: Current provider does not exist".red()
-                );
             }
-        } else {
-            println!("{}", "CCE configuration status:".cyan().bold());
-            println!("  Current provider: {}", "None selected".yellow());
-            if !config.providers.is_empty() {
-                println!("  Suggestion: Use 'cce use <provider-name>' to select a provider");
-            } else {
-                println!("  Suggestion: Use 'cce add' to add a service provider");
+            CheckStatus::NoProvider => {
+                println!("{}", "CCE configuration status:".accent().bold());
+                println!("  Current provider: {}", "None selected".warning());
+                if !config.providers.is_empty() {
+                    println!("  Suggestion: Use 'cce use <provider-name>' to select a provider");
+                } else {
+                    println!("  Suggestion: Use 'cce add' to add a service provider");
+                }
             }
         }
+    }
 
-        Ok(())
+    fn check_report(config: &Config, options: &OutputOptions) -> CheckReport {
+        let shell_provider = std::env::var(ENV_PROVIDER)
+            .ok()
+            .filter(|name| !name.is_empty());
+
+        // A shell that exported CCE_PROVIDER is checked against that provider,
+        // otherwise fall back to the persisted default
+        let checked = shell_provider.as_ref().or(config.current_provider.as_ref());
+        let provider = checked.and_then(|name| config.resolve_provider(name).ok());
+
+        // The token is in ANTHROPIC_API_KEY for providers using `auth = "api_key"`
+        let auth_var = provider
+            .as_ref()
+            .map_or(ENV_AUTH_TOKEN, |provider| provider.auth_style().env_var());
+        let current_api_key = std::env::var(auth_var).ok();
+        let current_api_url = std::env::var(ENV_BASE_URL).ok();
+
+        let status = match (checked, &provider) {
+            (None, _) => CheckStatus::NoProvider,
+            (Some(_), None) => CheckStatus::UnknownProvider,
+            (Some(_), Some(provider)) => {
+                // Verify if environment variables match configuration
+                let env_matches = current_api_key.as_deref() == Some(provider.token.as_str())
+                    && current_api_url.as_deref() == Some(provider.api_url.as_str());
+                if env_matches {
+                    CheckStatus::Match
+                } else {
+                    CheckStatus::Mismatch
+                }
+            }
+        };
+
+        let default_differs = shell_provider.is_some() && shell_provider != config.current_provider;
+
+        CheckReport {
+            auth_var,
+            auth_token: current_api_key.map(|key| options.token(&key)),
+            base_url: current_api_url,
+            model: std::env::var(ENV_MODEL).ok(),
+            shell_provider,
+            current_provider: config.current_provider.clone(),
+            default_differs,
+            configured_url: provider.map(|provider| provider.api_url.clone()),
+            status,
+        }
+    }
+
+    pub fn show_current(config: &Config, options: &OutputOptions) -> Result<()> {
+        let Some(name) = &config.current_provider else {
+            // Parsers still get a document, `null`, next to the exit status
+            if options.is_structured() {
+                options.emit(&None::<ProviderView>)?;
+            }
+            return Err(
+                CceError::NotFound("No service provider is currently active".to_string()).into(),
+            );
+        };
+        let provider = config.resolve_provider(name)?;
+
+        if options.is_structured() {
+            options.emit(&ProviderView::new(&provider, true, options))
+        } else {
+            println!("{}", provider.name);
+            Ok(())
+        }
     }
 
     pub fn clear_provider(config: &mut Config) -> Result<()> {
-        // Check if there's a current provider to clear
-        if config.current_provider.is_none() {
-            println!("{} No service provider is currently active", "ℹ️".blue());
-            return Ok(());
+        let shell_mode = Self::shell_integration_active();
+
+        // A shell can still carry a provider after another shell cleared the default
+        let shell_has_provider = shell_mode && std::env::var(ENV_PROVIDER).is_ok();
+        if config.current_provider.is_none() && !shell_has_provider {
+            return Err(
+                CceError::NotFound("No service provider is currently active".to_string()).into(),
+            );
         }
 
         let previous_provider = config.current_provider.clone();
 
         // Clear current provider in config
-        config.clear_current_provider();
-        config.save()?;
-
-        let shell_mode = Self::shell_integration_active();
+        if previous_provider.is_some() {
+            config.clear_current_provider();
+            config.save()?;
+        }
 
         if !shell_mode {
             if let Some(provider_name) = previous_provider {
-                println!("{} Cleared service provider configuration", "🧹".green());
+                println!(
+                    "{} Cleared service provider configuration",
+                    theme::CLEARED.as_str().success()
+                );
                 println!(
                     "{} Removed '{}' as the active provider",
-                    "✓".green(),
-                    provider_name.yellow()
+                    theme::DONE.as_str().success(),
+                    provider_name.warning()
                 );
             }
         }
@@ -261,7 +1154,7 @@ impl ProviderManager {
         if !shell_mode {
             println!(
                 "{}",
-                "Environment variables cleared from current session".green()
+                "Environment variables cleared from current session".success()
             );
         }
 
@@ -272,6 +1165,200 @@ impl ProviderManager {
         Ok(())
     }
 
+    pub fn export_providers(config: &Config, names: &[String], redact_tokens: bool) -> Result<()> {
+        let bundle = Self::export_bundle(config, names, redact_tokens)?;
+        print!("{}", bundle.to_toml()?);
+        Ok(())
+    }
+
+    /// The named providers, or all of them, with the providers they extend
+    fn export_bundle(config: &Config, names: &[String], redact_tokens: bool) -> Result<Bundle> {
+        let mut bundle = Bundle::default();
+
+        if names.is_empty() {
+            bundle.providers.extend(
+                config
+                    .providers
+                    .iter()
+                    .map(|(name, provider)| (name.clone(), provider.clone())),
+            );
+        } else {
+            for name in names {
+                let Some(provider) = config.providers.get(name) else {
+                    return Err(config.provider_not_found(name).into());
+                };
+                bundle.providers.insert(name.clone(), provider.clone());
+
+                // The bundle cannot resolve a provider without the ones it extends
+                let mut base = provider.extends.clone();
+                while let Some(name) = base.take() {
+                    let Some(provider) = config.providers.get(&name) else {
+                        break;
+                    };
+                    base = provider.extends.clone();
+                    if bundle.providers.insert(name, provider.clone()).is_some() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if redact_tokens {
+            for provider in bundle.providers.values_mut() {
+                provider.token.clear();
+            }
+        }
+
+        Ok(bundle)
+    }
+
+    pub fn import_providers(
+        config: &mut Config,
+        path: &Path,
+        replace: bool,
+        policy: ConflictPolicy,
+    ) -> Result<()> {
+        let bundle = Bundle::load(path)?;
+
+        if bundle.providers.is_empty() {
+            println!("{}", "Bundle contains no service providers".warning());
+            return Ok(());
+        }
+
+        // Prompts need stdin, which is unavailable when the bundle itself is piped in
+        let interactive = std::io::stdin().is_terminal() && path != Path::new("-");
+
+        let imported: Vec<String> = bundle.providers.keys().cloned().collect();
+        let (mut added, mut updated, mut skipped) = (0, 0, 0);
+
+        if replace {
+            let previous = std::mem::take(&mut config.providers);
+            for (name, mut provider) in bundle.providers {
+                // A redacted bundle never wipes out a token that was already entered
+                if !provider.has_token() {
+                    if let Some(old) = previous.get(&name) {
+                        provider.token = old.token.clone();
+                    }
+                }
+                config.providers.insert(name, provider);
+                added += 1;
+            }
+
+            if let Some(current) = &config.current_provider {
+                if !config.providers.contains_key(current) {
+                    config.clear_current_provider();
+                }
+            }
+        } else {
+            for (name, mut provider) in bundle.providers {
+                let Some(existing) = config.providers.get(&name) else {
+                    config.providers.insert(name, provider);
+                    added += 1;
+                    continue;
+                };
+
+                if !provider.has_token() {
+                    provider.token = existing.token.clone();
+                }
+                if *existing == provider {
+                    continue;
+                }
+
+                let overwrite = match policy {
+                    ConflictPolicy::Skip => false,
+                    ConflictPolicy::Overwrite => true,
+                    ConflictPolicy::Ask if interactive => {
+                        println!(
+                            "{} Service provider '{}' already exists",
+                            theme::WARNING.as_str().warning(),
+                            name.warning()
+                        );
+                        println!("    Local:  {}", existing.api_url.accent());
+                        println!("    Bundle: {}", provider.api_url.accent());
+                        let answer = Self::prompt_line("  Overwrite? [y/N] ")?;
+                        matches!(answer.as_str(), "y" | "Y" | "yes")
+                    }
+                    // Nobody to ask, so keep the local provider and say so where it is seen
+                    ConflictPolicy::Ask => {
+                        eprintln!(
+                            "{} Service provider '{}' already exists, keeping it since there is no terminal to ask (use --on-conflict to choose)",
+                            theme::WARNING.as_str().warning(),
+                            name.warning()
+                        );
+                        false
+                    }
+                };
+
+                if overwrite {
+                    config.providers.insert(name, provider);
+                    updated += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+
+        config.save()?;
+
+        println!(
+            "{} Imported service providers: {} added, {} updated, {} skipped",
+            theme::SUCCESS.as_str().success(),
+            added.to_string().success().bold(),
+            updated.to_string().success().bold(),
+            skipped.to_string().warning()
+        );
+
+        let missing: Vec<&String> = imported
+            .iter()
+            .filter(|name| {
+                config
+                    .providers
+                    .get(*name)
+                    .is_some_and(|provider| !provider.has_token())
+            })
+            .collect();
+        if !missing.is_empty() {
+            println!(
+                "{} No token yet for: {}",
+                theme::KEY.as_str().warning(),
+                missing
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .warning()
+            );
+            println!("   You will be asked for it on the first 'cce use <name>'");
+        }
+
+        Ok(())
+    }
+
+    /// Print a prompt on stderr and read one trimmed line from stdin
+    fn prompt_line(prompt: &str) -> Result<String> {
+        eprint!("{}", prompt);
+        std::io::stderr().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        Ok(input.trim().to_string())
+    }
+
+    /// Ask for a secret on the terminal without echoing it
+    /// Log a switch that has already happened; failing to log it must not undo it
+    fn record_switch(name: &str, scope: Scope) {
+        if let Err(err) = history::record(name, scope) {
+            eprintln!("{} {:#}", theme::WARNING.as_str().warning(), err);
+        }
+    }
+
+    fn prompt_secret(prompt: &str) -> Result<String> {
+        let input = rpassword::prompt_password(prompt).with_context(|| {
+            "Failed to read token from the terminal, pass it with --token-stdin instead"
+        })?;
+        Ok(input.trim().to_string())
+    }
+
     fn emit_export_commands(provider: &Provider) {
         println!("{}", generate_export_commands(provider));
     }
@@ -293,7 +1380,7 @@ impl ProviderManager {
 
         // Detect shell type
         let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-        let shell_name = shell.split('/').last().unwrap_or("bash");
+        let shell_name = shell.split('/').next_back().unwrap_or("bash");
 
         let (config_file, comment_prefix) = match shell_name {
             "zsh" => ("~/.zshrc", "#"),
@@ -303,10 +1390,10 @@ impl ProviderManager {
         };
 
         // Expand tilde
-        let config_path = if config_file.starts_with("~/") {
+        let config_path = if let Some(relative) = config_file.strip_prefix("~/") {
             let home = dirs::home_dir()
                 .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
-            home.join(&config_file[2..])
+            home.join(relative)
         } else {
             std::path::PathBuf::from(config_file)
         };
@@ -333,15 +1420,15 @@ impl ProviderManager {
         if already_installed && !force {
             println!(
                 "{} Shell integration is already installed in {}",
-                "ℹ️".blue(),
-                config_file.cyan()
+                theme::INFO.as_str().info(),
+                config_file.accent()
             );
             println!(
                 "{} Use {} to reinstall",
-                "💡".blue(),
-                "cce install --force".yellow()
+                theme::HINT.as_str().info(),
+                "cce install --force".warning()
             );
-            return Ok(());
+            return Self::offer_completions(&config_path, shell_name);
         }
 
         // Create config directory if it doesn't exist (for fish)
@@ -364,15 +1451,63 @@ impl ProviderManager {
 
         writeln!(file, "{}", integration_block)?;
 
-        println!("{} Shell integration installed successfully!", "✅".green());
-        println!("📄 Added to: {}", config_path.display().to_string().cyan());
+        println!(
+            "{} Shell integration installed successfully!",
+            theme::SUCCESS.as_str().success()
+        );
+        println!(
+            "{} Added to: {}",
+            theme::FILE,
+            config_path.display().to_string().accent()
+        );
         println!();
-        println!("{} To activate in current terminal:", "🔄".blue().bold());
-        println!("   {}", format!("source {}", config_file).yellow());
+        println!(
+            "{} To activate in current terminal:",
+            theme::SWITCH.as_str().info().bold()
+        );
+        println!("   {}", format!("source {}", config_file).warning());
         println!();
         println!(
             "{} Or restart your terminal for changes to take effect.",
-            "🆕".blue().bold()
+            theme::NEW.as_str().info().bold()
+        );
+
+        Self::offer_completions(&config_path, shell_name)
+    }
+
+    /// Offer to load shell completions from the profile, unless it already does
+    fn offer_completions(config_path: &Path, shell_name: &str) -> Result<()> {
+        let Some(line) = completions::profile_line(shell_name) else {
+            return Ok(());
+        };
+        let profile = std::fs::read_to_string(config_path).unwrap_or_default();
+        if profile.lines().any(|existing| existing.trim() == line) {
+            return Ok(());
+        }
+
+        println!();
+        if !std::io::stdin().is_terminal() {
+            println!(
+                "{} Add {} to {} for tab completion of provider names",
+                theme::HINT.as_str().info(),
+                line.warning(),
+                config_path.display()
+            );
+            return Ok(());
+        }
+
+        let answer = Self::prompt_line(
+            "Install tab completion for cce commands and provider names? [Y/n] ",
+        )?;
+        if answer.eq_ignore_ascii_case("n") || answer.eq_ignore_ascii_case("no") {
+            return Ok(());
+        }
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(config_path)?;
+        writeln!(file, "\n# CCE Completions\n{}", line)?;
+        println!(
+            "{} Shell completions installed",
+            theme::SUCCESS.as_str().success()
         );
 
         Ok(())
@@ -389,42 +1524,179 @@ impl ProviderManager {
             r#"cce() {{
     local cce_binary="{}"
 
-    if [[ "$1" == "use" && -n "$2" ]]; then
+    if [[ "$1" == "use" || "$1" == "model" || "$1" == "clear" ]] &&
+        [[ " $* " != *" -h "* && " $* " != *" --help "* ]]; then
+        # Only shell commands reach stdout; errors and the provider picker go to the terminal,
+        # and the exit code tells what went wrong (see `cce --help`)
         local env_output
-        env_output=$(CCE_SHELL_INTEGRATION=1 "$cce_binary" use "$2" 2>/dev/null)
-        if [[ $? -eq 0 && -n "$env_output" ]]; then
-            eval "$env_output"
-            echo "⚡ Switched to service provider '$2'"
-            echo "✅ Environment variables are now active in current terminal"
-        else
-            "$cce_binary" "$@"
-        fi
-    elif [[ "$1" == "clear" ]]; then
-        local env_output
-        env_output=$(CCE_SHELL_INTEGRATION=1 "$cce_binary" clear 2>/dev/null)
-        if [[ $? -eq 0 && -n "$env_output" ]]; then
-            eval "$env_output"
-            echo "🧹 Cleared service provider configuration"
-            echo "✅ Environment variables are now unset in current terminal"
-        else
-            "$cce_binary" "$@"
+        env_output=$(CCE_SHELL_INTEGRATION=1 "$cce_binary" "$@") || return
+        eval "$env_output"
+        case "$1" in
+            use)
+                echo "{switched} Switched to service provider '$CCE_PROVIDER'"
+                echo "{success} Environment variables are now active in current terminal"
+                ;;
+            model)
+                echo "{model} Switched '$CCE_PROVIDER' to model '$ANTHROPIC_MODEL'"
+                ;;
+            clear)
+                echo "{cleared} Cleared service provider configuration"
+                echo "{success} Environment variables are now unset in current terminal"
+                ;;
+        esac
+    elif [[ "$1" == "tui" ]]; then
+        # The TUI owns the terminal, so it leaves the commands for this shell in a file
+        local handoff status
+        handoff=$(mktemp "${{TMPDIR:-/tmp}}/cce-tui.XXXXXX") || return
+        CCE_TUI_HANDOFF="$handoff" "$cce_binary" "$@"
+        status=$?
+        if [[ -s "$handoff" ]]; then
+            eval "$(<"$handoff")"
+            if [[ -n "$CCE_PROVIDER" ]]; then
+                echo "{switched} Switched to service provider '$CCE_PROVIDER'"
+            else
+                echo "{cleared} Cleared service provider configuration"
+            fi
         fi
+        rm -f "$handoff"
+        return $status
     else
         "$cce_binary" "$@"
     fi
 }}
 
-# Auto-load current provider on shell startup
-if [[ -f ~/.cce/config.toml ]]; then
-    _cce_current=$(awk -F\" '/^current_provider/ {{print $2}}' ~/.cce/config.toml)
-    if [[ -n "$_cce_current" ]]; then
-        eval "$(CCE_SHELL_INTEGRATION=1 "{}" use "$_cce_current" 2>/dev/null)"
-    fi
-    unset _cce_current
-fi"#,
-            cce_path, cce_path
+# Load the default provider on shell startup, or the one picked by the rules in auto mode
+eval "$("{}" auto --print 2>/dev/null)""#,
+            cce_path,
+            cce_path,
+            switched = theme::SWITCHED,
+            success = theme::SUCCESS,
+            model = theme::MODEL,
+            cleared = theme::CLEARED
         );
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestHome;
+    use std::fs;
+
+    fn config_with(names: &[&str]) -> Config {
+        let mut config = Config::default();
+        for name in names {
+            config.add_provider(
+                name.to_string(),
+                format!("https://{}.example.com", name),
+                format!("sk-{}", name),
+                None,
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn check_reads_the_token_from_the_providers_auth_variable() {
+        // Tests holding a home take turns, which covers the variables set here too
+        let _home = TestHome::new();
+        let mut config = config_with(&["direct"]);
+        let provider = config.providers.get_mut("direct").unwrap();
+        provider.auth = Some(AuthStyle::ApiKey);
+        config.current_provider = Some("direct".to_string());
+
+        // What `cce use direct` exports
+        std::env::remove_var(ENV_PROVIDER);
+        std::env::remove_var(ENV_AUTH_TOKEN);
+        std::env::set_var(ENV_API_KEY, "sk-direct");
+        std::env::set_var(ENV_BASE_URL, "https://direct.example.com");
+        let options = OutputOptions {
+            format: OutputFormat::Plain,
+            show_secrets: true,
+        };
+        let report = ProviderManager::check_report(&config, &options);
+        std::env::remove_var(ENV_API_KEY);
+        std::env::remove_var(ENV_BASE_URL);
+
+        assert!(report.status == CheckStatus::Match);
+        assert_eq!(report.auth_var, ENV_API_KEY);
+        assert_eq!(report.auth_token.as_deref(), Some("sk-direct"));
+        assert!(report
+            .plain_lines()
+            .contains(&"auth_var=ANTHROPIC_API_KEY".to_string()));
+    }
+
+    #[test]
+    fn exports_the_bases_of_the_chosen_providers() {
+        let mut config = config_with(&["gateway", "other"]);
+        config.providers.insert(
+            "child".to_string(),
+            Provider {
+                name: "child".to_string(),
+                extends: Some("gateway".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let bundle = ProviderManager::export_bundle(&config, &["child".to_string()], true).unwrap();
+        let names: Vec<&String> = bundle.providers.keys().collect();
+        assert_eq!(names, ["child", "gateway"]);
+        assert!(bundle
+            .providers
+            .values()
+            .all(|provider| !provider.has_token()));
+
+        let err = ProviderManager::export_bundle(&config, &["nope".to_string()], false);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn merging_a_redacted_bundle_keeps_local_tokens() {
+        let home = TestHome::new();
+        let path = home.path().join("team.toml");
+        let mut config = config_with(&["gateway", "mine"]);
+        config.current_provider = Some("mine".to_string());
+
+        let mut team = config_with(&["gateway", "new"]);
+        team.providers.get_mut("gateway").unwrap().api_url = "https://moved.example.com".into();
+        let names = vec!["gateway".to_string(), "new".to_string()];
+        let bundle = ProviderManager::export_bundle(&team, &names, true).unwrap();
+        fs::write(&path, bundle.to_toml().unwrap()).unwrap();
+
+        ProviderManager::import_providers(&mut config, &path, false, ConflictPolicy::Overwrite)
+            .unwrap();
+        let gateway = &config.providers["gateway"];
+        assert_eq!(gateway.api_url, "https://moved.example.com");
+        assert_eq!(gateway.token, "sk-gateway");
+        assert!(!config.providers["new"].has_token());
+        assert!(config.providers.contains_key("mine"));
+
+        // Replacing drops what the bundle lacks, including the current provider
+        ProviderManager::import_providers(&mut config, &path, true, ConflictPolicy::Ask).unwrap();
+        let mut names: Vec<&String> = config.providers.keys().collect();
+        names.sort();
+        assert_eq!(names, ["gateway", "new"]);
+        assert_eq!(config.providers["gateway"].token, "sk-gateway");
+        assert_eq!(config.current_provider, None);
+    }
+
+    #[test]
+    fn skipping_conflicts_leaves_existing_providers_alone() {
+        let home = TestHome::new();
+        let path = home.path().join("team.toml");
+        fs::write(
+            &path,
+            "[providers.gateway]\nname = \"gateway\"\napi_url = \"https://moved.example.com\"\n",
+        )
+        .unwrap();
+
+        let mut config = config_with(&["gateway"]);
+        ProviderManager::import_providers(&mut config, &path, false, ConflictPolicy::Skip).unwrap();
+        assert_eq!(
+            config.providers["gateway"].api_url,
+            "https://gateway.example.com"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::config::{Config, Rule};
+use crate::error::CceError;
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDateTime, Timelike, Weekday};
+use serde::Serialize;
+
+/// Provider `cce auto` picks and why
+#[derive(Serialize)]
+pub struct AutoChoice {
+    pub provider: String,
+    /// Index into `rules` of the matching rule, `None` when falling back to `current_provider`
+    pub rule: Option<usize>,
+}
+
+/// Provider that should be active now: the first matching rule, else `current_provider`
+pub fn active_provider(config: &Config) -> Result<Option<AutoChoice>> {
+    let now = Local::now().naive_local();
+
+    // Check every rule so mistakes surface even when an earlier rule matches
+    let mut matched = None;
+    for (index, rule) in config.rules.iter().enumerate() {
+        if !config.providers.contains_key(&rule.provider) {
+            return Err(CceError::ConfigInvalid(format!(
+                "Rule {} refers to service provider '{}', which does not exist",
+                index + 1,
+                rule.provider
+            ))
+            .into());
+        }
+        if rule_matches(rule, now)? && matched.is_none() {
+            matched = Some(AutoChoice {
+                provider: rule.provider.clone(),
+                rule: Some(index),
+            });
+        }
+    }
+
+    Ok(matched.or_else(|| {
+        config.current_provider.clone().map(|provider| AutoChoice {
+            provider,
+            rule: None,
+        })
+    }))
+}
+
+/// Whether `rule` applies at `now`.
+///
+/// A window crossing midnight, e.g. `22:00-08:00`, belongs to the day it starts on,
+/// so `fri` covers Friday night until Saturday morning.
+fn rule_matches(rule: &Rule, now: NaiveDateTime) -> Result<bool> {
+    let minute = now.hour() * 60 + now.minute();
+    let mut day = now.weekday();
+
+    if let Some(hours) = &rule.hours {
+        let (start, end) = parse_hours(hours)?;
+        let in_window = if start <= end {
+            (start..end).contains(&minute)
+        } else if minute >= start {
+            true
+        } else if minute < end {
+            // Early morning part of last night's window
+            day = day.pred();
+            true
+        } else {
+            false
+        };
+        if !in_window {
+            return Ok(false);
+        }
+    }
+
+    match &rule.days {
+        Some(days) => Ok(parse_days(days)?.contains(&day)),
+        None => Ok(true),
+    }
+}
+
+/// Parse `mon-fri`, `sat,sun` or `fri-mon` into the days they cover
+fn parse_days(spec: &str) -> Result<Vec<Weekday>> {
+    let invalid = || {
+        CceError::ConfigInvalid(format!(
+            "Invalid rule days '{}', expected e.g. 'mon-fri' or 'sat,sun'",
+            spec
+        ))
+    };
+    // Accepts short and full English names in any case
+    let weekday = |name: &str| name.trim().parse::<Weekday>().ok();
+
+    let mut days = Vec::new();
+    for part in spec.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let mut day = weekday(from).ok_or_else(invalid)?;
+                let to = weekday(to).ok_or_else(invalid)?;
+                days.push(day);
+                // Ranges may wrap around the weekend, e.g. fri-mon
+                while day != to {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(weekday(part).ok_or_else(invalid)?),
+        }
+    }
+    Ok(days)
+}
+
+/// Parse `HH:MM-HH:MM` into minutes since midnight
+fn parse_hours(spec: &str) -> Result<(u32, u32)> {
+    let invalid = || {
+        CceError::ConfigInvalid(format!(
+            "Invalid rule hours '{}', expected e.g. '22:00-08:00'",
+            spec
+        ))
+    };
+    let minutes = |time: &str| {
+        let (hour, minute) = time.trim().split_once(':')?;
+        let (hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+        // 24:00 closes a window at midnight
+        (hour < 24 && minute < 60 || hour == 24 && minute == 0).then_some(hour * 60 + minute)
+    };
+
+    let (start, end) = spec.split_once('-').ok_or_else(invalid)?;
+    let start = minutes(start).ok_or_else(invalid)?;
+    let end = minutes(end).ok_or_else(invalid)?;
+    if start == end {
+        return Err(invalid().into());
+    }
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn rule(days: Option<&str>, hours: Option<&str>) -> Rule {
+        Rule {
+            provider: "cheap".to_string(),
+            days: days.map(str::to_string),
+            hours: hours.map(str::to_string),
+        }
+    }
+
+    /// 2026-10-16 is a Friday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_day_ranges_and_lists() {
+        use Weekday::*;
+        assert_eq!(parse_days("mon-fri").unwrap(), [Mon, Tue, Wed, Thu, Fri]);
+        assert_eq!(parse_days("sat, sun").unwrap(), [Sat, Sun]);
+        assert_eq!(parse_days("Fri-Mon").unwrap(), [Fri, Sat, Sun, Mon]);
+        assert_eq!(parse_days("monday,wed-thu").unwrap(), [Mon, Wed, Thu]);
+        assert!(parse_days("mon-funday").is_err());
+        assert!(parse_days("").is_err());
+    }
+
+    #[test]
+    fn parses_hour_windows() {
+        assert_eq!(parse_hours("22:00-08:00").unwrap(), (1320, 480));
+        assert_eq!(parse_hours("09:30 - 17:45").unwrap(), (570, 1065));
+        assert_eq!(parse_hours("18:00-24:00").unwrap(), (1080, 1440));
+        assert!(parse_hours("08:00-08:00").is_err());
+        assert!(parse_hours("25:00-08:00").is_err());
+        assert!(parse_hours("08:60-09:00").is_err());
+        assert!(parse_hours("8-9").is_err());
+    }
+
+    #[test]
+    fn window_across_midnight_belongs_to_the_day_it_starts_on() {
+        let friday_night = rule(Some("fri"), Some("22:00-08:00"));
+        assert!(rule_matches(&friday_night, at(16, 23, 0)).unwrap());
+        // Saturday morning is still Friday's window
+        assert!(rule_matches(&friday_night, at(17, 7, 59)).unwrap());
+        assert!(!rule_matches(&friday_night, at(17, 8, 0)).unwrap());
+        assert!(!rule_matches(&friday_night, at(17, 23, 0)).unwrap());
+        // Friday morning is Thursday's window
+        assert!(!rule_matches(&friday_night, at(16, 7, 0)).unwrap());
+    }
+
+    #[test]
+    fn rules_without_days_or_hours_always_match() {
+        assert!(rule_matches(&rule(None, None), at(18, 12, 0)).unwrap());
+        assert!(rule_matches(&rule(None, Some("09:00-17:00")), at(18, 12, 0)).unwrap());
+        assert!(!rule_matches(&rule(None, Some("09:00-17:00")), at(18, 17, 0)).unwrap());
+        assert!(rule_matches(&rule(Some("sat,sun"), None), at(18, 3, 0)).unwrap());
+    }
+}
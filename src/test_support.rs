@@ -0,0 +1,40 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// `HOME` belongs to the whole process, so tests that need their own take turns
+static HOME_LOCK: Mutex<()> = Mutex::new(());
+
+/// A scratch home directory for one test, where the config, history and trash end up.
+/// Removed again when dropped.
+pub struct TestHome {
+    path: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TestHome {
+    pub fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        // A failed test poisons the lock, which says nothing about the next one
+        let lock = HOME_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let path = std::env::temp_dir().join(format!(
+            "cce-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        std::env::set_var("HOME", &path);
+        Self { path, _lock: lock }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestHome {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::config::{ThemePreset, ThemeSettings};
+use colored::{ColoredString, Colorize};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::{border, scrollbar};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Colors by role, shared by the TUI and the colored command output
+pub struct Theme {
+    /// Titles, provider names and URLs
+    pub accent: Color,
+    /// The active provider and finished actions
+    pub success: Color,
+    /// Warnings, key hints and values worth a look
+    pub warning: Color,
+    pub error: Color,
+    /// Hints, tags and groups
+    pub info: Color,
+    pub model: Color,
+    /// Characters matched by a search and revealed tokens
+    pub highlight: Color,
+    /// Field names in the detail pane
+    pub label: Color,
+    /// Masked tokens, unset values and other secondary text
+    pub muted: Color,
+    /// Plain text such as inactive provider names
+    pub text: Color,
+    /// The selected row of a list
+    pub selection: Style,
+    /// Popups asking to confirm something destructive
+    pub alert: Style,
+    /// ASCII markers and borders instead of emoji and box drawing
+    pub ascii: bool,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Set up the theme from the `[theme]` section. Colors are off with `--no-color` or when
+/// `NO_COLOR` is set; ASCII markers are used when asked for or when the locale is not UTF-8.
+pub fn init(settings: &ThemeSettings, no_color: bool) {
+    let no_color = no_color || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    if no_color {
+        colored::control::set_override(false);
+    }
+    let ascii = settings.ascii.unwrap_or_else(|| !unicode_supported());
+    let _ = THEME.set(Theme::new(settings.preset, no_color, ascii));
+}
+
+/// The theme set up by `init`, or the dark preset before that
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(|| Theme::new(ThemePreset::Dark, false, false))
+}
+
+impl Theme {
+    fn new(preset: ThemePreset, no_color: bool, ascii: bool) -> Self {
+        let selected =
+            |background: Color| Style::default().bg(background).add_modifier(Modifier::BOLD);
+        let alert = Style::default().bg(Color::Red).fg(Color::White);
+        if no_color {
+            return Self {
+                accent: Color::Reset,
+                success: Color::Reset,
+                warning: Color::Reset,
+                error: Color::Reset,
+                info: Color::Reset,
+                model: Color::Reset,
+                highlight: Color::Reset,
+                label: Color::Reset,
+                muted: Color::Reset,
+                text: Color::Reset,
+                selection: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+                alert: Style::default().add_modifier(Modifier::REVERSED),
+                ascii,
+            };
+        }
+
+        match preset {
+            ThemePreset::Dark => Self {
+                accent: Color::Cyan,
+                success: Color::Green,
+                warning: Color::Yellow,
+                error: Color::Red,
+                info: Color::Blue,
+                model: Color::Magenta,
+                highlight: Color::LightRed,
+                label: Color::Gray,
+                muted: Color::DarkGray,
+                text: Color::White,
+                selection: selected(Color::DarkGray),
+                alert,
+                ascii,
+            },
+            // Yellow, cyan and white wash out on a light background
+            ThemePreset::Light => Self {
+                accent: Color::Blue,
+                success: Color::Green,
+                warning: Color::Magenta,
+                error: Color::Red,
+                info: Color::Blue,
+                model: Color::Magenta,
+                highlight: Color::Red,
+                label: Color::DarkGray,
+                muted: Color::DarkGray,
+                text: Color::Reset,
+                selection: selected(Color::Gray),
+                alert,
+                ascii,
+            },
+            ThemePreset::HighContrast => Self {
+                accent: Color::LightCyan,
+                success: Color::LightGreen,
+                warning: Color::LightYellow,
+                error: Color::LightRed,
+                info: Color::LightBlue,
+                model: Color::LightMagenta,
+                highlight: Color::LightYellow,
+                label: Color::White,
+                muted: Color::White,
+                text: Color::White,
+                selection: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+                alert: alert.add_modifier(Modifier::BOLD),
+                ascii,
+            },
+        }
+    }
+
+    /// Lines for block borders
+    pub fn border_set(&self) -> border::Set {
+        if self.ascii {
+            border::Set {
+                top_left: "+",
+                top_right: "+",
+                bottom_left: "+",
+                bottom_right: "+",
+                vertical_left: "|",
+                vertical_right: "|",
+                horizontal_top: "-",
+                horizontal_bottom: "-",
+            }
+        } else {
+            border::PLAIN
+        }
+    }
+
+    pub fn scrollbar_set(&self) -> scrollbar::Set {
+        if self.ascii {
+            scrollbar::Set {
+                track: "|",
+                thumb: "#",
+                begin: "^",
+                end: "v",
+            }
+        } else {
+            scrollbar::DOUBLE_VERTICAL
+        }
+    }
+}
+
+/// Whether the terminal can be expected to show emoji and other non-ASCII symbols
+fn unicode_supported() -> bool {
+    if cfg!(windows) {
+        // The legacy console lacks the fonts; Windows Terminal and most others have them
+        return std::env::var_os("WT_SESSION").is_some()
+            || std::env::var_os("TERM_PROGRAM").is_some();
+    }
+    if std::env::var("TERM").is_ok_and(|term| term == "linux") {
+        return false;
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        None => true,
+    }
+}
+
+/// Theme colors for `colored` output, by role
+pub trait Paint: Colorize + Into<ColoredString> + Sized {
+    fn accent(self) -> ColoredString {
+        paint(self, current().accent)
+    }
+
+    fn success(self) -> ColoredString {
+        paint(self, current().success)
+    }
+
+    fn warning(self) -> ColoredString {
+        paint(self, current().warning)
+    }
+
+    fn error(self) -> ColoredString {
+        paint(self, current().error)
+    }
+
+    fn info(self) -> ColoredString {
+        paint(self, current().info)
+    }
+
+    fn muted(self) -> ColoredString {
+        paint(self, current().muted)
+    }
+
+    fn plain(self) -> ColoredString {
+        paint(self, current().text)
+    }
+}
+
+impl Paint for &str {}
+impl Paint for ColoredString {}
+
+fn paint<S: Paint>(text: S, color: Color) -> ColoredString {
+    use colored::Color as Ansi;
+    let color = match color {
+        Color::Black => Ansi::Black,
+        Color::Red => Ansi::Red,
+        Color::Green => Ansi::Green,
+        Color::Yellow => Ansi::Yellow,
+        Color::Blue => Ansi::Blue,
+        Color::Magenta => Ansi::Magenta,
+        Color::Cyan => Ansi::Cyan,
+        Color::Gray => Ansi::White,
+        Color::DarkGray => Ansi::BrightBlack,
+        Color::LightRed => Ansi::BrightRed,
+        Color::LightGreen => Ansi::BrightGreen,
+        Color::LightYellow => Ansi::BrightYellow,
+        Color::LightBlue => Ansi::BrightBlue,
+        Color::LightMagenta => Ansi::BrightMagenta,
+        Color::LightCyan => Ansi::BrightCyan,
+        Color::White => Ansi::BrightWhite,
+        Color::Rgb(r, g, b) => Ansi::TrueColor { r, g, b },
+        Color::Reset | Color::Indexed(_) => return text.into(),
+    };
+    text.color(color)
+}
+
+/// A marker symbol with a stand-in for terminals that cannot show it
+pub struct Glyph {
+    unicode: &'static str,
+    ascii: &'static str,
+}
+
+impl Glyph {
+    pub fn as_str(&self) -> &'static str {
+        if current().ascii {
+            self.ascii
+        } else {
+            self.unicode
+        }
+    }
+}
+
+impl fmt::Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+const fn glyph(unicode: &'static str, ascii: &'static str) -> Glyph {
+    Glyph { unicode, ascii }
+}
+
+pub const ACTIVE: Glyph = glyph("●", "*");
+pub const INACTIVE: Glyph = glyph("○", "o");
+pub const SUCCESS: Glyph = glyph("✅", "[ok]");
+pub const DONE: Glyph = glyph("✓", "[ok]");
+pub const ERROR: Glyph = glyph("❌", "[x]");
+pub const WARNING: Glyph = glyph("⚠️", "[!]");
+pub const INFO: Glyph = glyph("ℹ️", "[i]");
+pub const HINT: Glyph = glyph("💡", "[tip]");
+pub const SWITCH: Glyph = glyph("🔄", "[>]");
+pub const SWITCHED: Glyph = glyph("⚡", "[>]");
+pub const MODEL: Glyph = glyph("🎯", "[model]");
+pub const TAG: Glyph = glyph("🏷️", "[tag]");
+pub const GROUP: Glyph = glyph("📁", "[group]");
+pub const DELETED: Glyph = glyph("🗑️", "[deleted]");
+pub const RESTORED: Glyph = glyph("♻️", "[restored]");
+pub const AUTO: Glyph = glyph("🕒", "[auto]");
+pub const CHECK: Glyph = glyph("🔍", "[check]");
+pub const CLEARED: Glyph = glyph("🧹", "[cleared]");
+pub const KEY: Glyph = glyph("🔑", "[key]");
+pub const FILE: Glyph = glyph("📄", "[file]");
+pub const NEW: Glyph = glyph("🆕", "[new]");
+pub const CHECKING: Glyph = glyph("…", "...");
+pub const REJECTED: Glyph = glyph("▲", "!");
+pub const DOWN: Glyph = glyph("✗", "x");
+pub const ARROW_UP: Glyph = glyph("↑", "Up");
+pub const ARROW_DOWN: Glyph = glyph("↓", "Down");
+pub const ARROW_LEFT: Glyph = glyph("←", "Left");
+pub const ARROW_RIGHT: Glyph = glyph("→", "Right");
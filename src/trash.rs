@@ -0,0 +1,145 @@
+use crate::config::{Config, Provider};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Deleted providers under the config directory, oldest first, for `cce restore`
+const TRASH_FILE: &str = "trash.toml";
+
+/// Deletions kept; older ones are dropped for good
+const MAX_ENTRIES: usize = 50;
+
+#[derive(Serialize, Deserialize, Default)]
+struct Trash {
+    #[serde(default)]
+    deleted: Vec<TrashEntry>,
+}
+
+/// A provider as it was when deleted, token included
+#[derive(Serialize, Deserialize)]
+struct TrashEntry {
+    /// Unix timestamp of the deletion
+    deleted_at: u64,
+    provider: Provider,
+}
+
+impl Trash {
+    fn load() -> Result<Self> {
+        let path = Config::config_dir()?.join(TRASH_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read trash file: {:?}", path))?;
+        toml::from_str(&content).with_context(|| format!("Invalid trash file: {:?}", path))
+    }
+
+    fn save(&self) -> Result<()> {
+        let dir = Config::config_dir()?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create config directory: {:?}", dir))?;
+        let path = dir.join(TRASH_FILE);
+        let content = toml::to_string_pretty(self).with_context(|| "Failed to serialize trash")?;
+        fs::write(&path, content).with_context(|| format!("Failed to write trash file: {:?}", path))
+    }
+
+    /// Index of the latest deletion of `name`
+    fn position(&self, name: &str) -> Option<usize> {
+        self.deleted
+            .iter()
+            .rposition(|entry| entry.provider.name == name)
+    }
+}
+
+/// Keep a deleted provider so it can be restored later
+pub fn put(provider: Provider) -> Result<()> {
+    let mut trash = Trash::load()?;
+    trash.deleted.push(TrashEntry {
+        deleted_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        provider,
+    });
+    let skip = trash.deleted.len().saturating_sub(MAX_ENTRIES);
+    trash.deleted.drain(..skip);
+    trash.save()
+}
+
+/// The most recently deleted provider named `name`, left in the trash
+pub fn find(name: &str) -> Result<Option<Provider>> {
+    let trash = Trash::load()?;
+    Ok(trash
+        .position(name)
+        .map(|index| trash.deleted[index].provider.clone()))
+}
+
+/// Drop the most recently deleted provider named `name`, once it has been restored
+pub fn remove(name: &str) -> Result<()> {
+    let mut trash = Trash::load()?;
+    if let Some(index) = trash.position(name) {
+        trash.deleted.remove(index);
+        trash.save()?;
+    }
+    Ok(())
+}
+
+/// Names of the deleted providers, most recent first and without repeats
+pub fn names() -> Result<Vec<String>> {
+    let trash = Trash::load()?;
+    let mut names: Vec<String> = Vec::new();
+    for entry in trash.deleted.into_iter().rev() {
+        if !names.contains(&entry.provider.name) {
+            names.push(entry.provider.name);
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestHome;
+
+    fn provider(name: &str, api_url: &str) -> Provider {
+        Provider {
+            name: name.to_string(),
+            api_url: api_url.to_string(),
+            token: "sk-test".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn restores_the_latest_deletion_first() {
+        let _home = TestHome::new();
+        assert!(find("kimi").unwrap().is_none());
+
+        put(provider("kimi", "https://old.example.com")).unwrap();
+        put(provider("glm", "https://glm.example.com")).unwrap();
+        put(provider("kimi", "https://new.example.com")).unwrap();
+        assert_eq!(names().unwrap(), ["kimi", "glm"]);
+
+        let found = find("kimi").unwrap().unwrap();
+        assert_eq!(found.api_url, "https://new.example.com");
+        assert_eq!(found.token, "sk-test");
+
+        remove("kimi").unwrap();
+        assert_eq!(
+            find("kimi").unwrap().unwrap().api_url,
+            "https://old.example.com"
+        );
+    }
+
+    #[test]
+    fn keeps_only_the_latest_deletions() {
+        let _home = TestHome::new();
+        for index in 0..MAX_ENTRIES + 5 {
+            put(provider(&format!("p{}", index), "https://example.com")).unwrap();
+        }
+        let names = names().unwrap();
+        assert_eq!(names.len(), MAX_ENTRIES);
+        assert_eq!(names.last().unwrap(), "p5");
+    }
+}
@@ -1,34 +1,161 @@
-use crate::config::{Config, Provider};
+use crate::clipboard;
+use crate::config::{validate_api_url, Config, Provider};
 use crate::constants::*;
-use anyhow::Result;
+use crate::fuzzy;
+use crate::health::{self, Health};
+use crate::history::{self, Scope};
+use crate::keys::{Action, KeyBinding, KeyMap};
+use crate::line_input::LineInput;
+use crate::models::{self, ModelList};
+use crate::output::mask_token;
+use crate::presets::{Preset, PRESETS};
+use crate::theme;
+use crate::trash;
+use anyhow::{Context, Result};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Wrap,
+    },
     Frame, Terminal,
 };
+use std::collections::HashMap;
+use std::fs;
 use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
 
 enum InputMode {
     Normal,
+    /// Typing into the filter bar opened with `/`
+    Search,
+    /// Picking a preset before the add form; 0 is "Custom", then `PRESETS` in order
+    PresetPicker(usize),
     AddProvider(AddProviderState),
+    /// Waiting for the gateway to list its models, before the model picker opens
+    LoadingModels {
+        provider: String,
+        current: Option<String>,
+    },
+    /// Picking a new model for an existing provider
+    ModelPicker {
+        provider: String,
+        models: Vec<String>,
+        selected: usize,
+    },
+    /// Typing a model for an existing provider whose gateway cannot list them
+    ModelInput {
+        provider: String,
+        input: LineInput,
+    },
     DeleteConfirm,
 }
 
+/// A change that `Ctrl-Z` or `U` takes back
+enum Undo {
+    /// A deleted provider, as it was stored, whether it was the current one
+    /// and the groups it was the default of
+    Delete {
+        provider: Box<Provider>,
+        was_current: bool,
+        default_of: Vec<String>,
+    },
+    /// The current provider before a switch or clear
+    Current(Option<String>),
+}
+
+/// What `y`, `Y` and `e` put on the clipboard
+enum CopyTarget {
+    Token,
+    Url,
+    Exports,
+}
+
 #[derive(Default)]
 struct AddProviderState {
     current_field: usize, // 0: name, 1: url, 2: token, 3: model (optional)
-    name: String,
-    url: String,
-    token: String,
-    model: String,
+    name: LineInput,
+    url: LineInput,
+    token: LineInput,
+    model: LineInput,
+    preset: Option<&'static Preset>,
+    /// Models offered by the gateway and the highlighted one, while the picker is open
+    model_picker: Option<(Vec<String>, usize)>,
+    /// Whether the gateway's models are being loaded for the picker
+    loading_models: bool,
+    /// Set after a save attempt, so empty required fields report errors from then on
+    submitted: bool,
+}
+
+/// Number of fields in the add form
+const ADD_FORM_FIELDS: usize = 4;
+
+impl AddProviderState {
+    /// Form prefilled from a preset, leaving the name for the user
+    fn from_preset(preset: &'static Preset) -> Self {
+        Self {
+            url: LineInput::new(preset.api_url),
+            token: LineInput::new(preset.default_token.unwrap_or_default()),
+            model: LineInput::new(preset.model.unwrap_or_default()),
+            preset: Some(preset),
+            ..Default::default()
+        }
+    }
+
+    fn field(&self, field: usize) -> &LineInput {
+        match field {
+            0 => &self.name,
+            1 => &self.url,
+            2 => &self.token,
+            _ => &self.model,
+        }
+    }
+
+    fn current_field_mut(&mut self) -> &mut LineInput {
+        match self.current_field {
+            0 => &mut self.name,
+            1 => &mut self.url,
+            2 => &mut self.token,
+            _ => &mut self.model,
+        }
+    }
+
+    /// Problem with the value of `field`, if any
+    fn field_error(&self, field: usize, config: &Config) -> Option<String> {
+        let value = self.field(field).as_str().trim();
+        match field {
+            0 if value.is_empty() => Some("Name is required".to_string()),
+            0 if config.providers.contains_key(value) => {
+                Some(format!("Provider '{}' already exists", value))
+            }
+            1 if value.is_empty() => Some("API URL is required".to_string()),
+            1 => validate_api_url(value).err().map(|err| err.to_string()),
+            2 if value.is_empty() => Some("Token is required".to_string()),
+            _ => None,
+        }
+    }
+
+    /// Error to show under `field`; empty fields only complain once saving was attempted
+    fn visible_error(&self, field: usize, config: &Config) -> Option<String> {
+        if self.field(field).is_empty() && !self.submitted {
+            return None;
+        }
+        self.field_error(field, config)
+    }
 }
 
 pub struct TuiApp {
@@ -37,31 +164,218 @@ pub struct TuiApp {
     input_mode: InputMode,
     message: Option<String>,
     message_is_error: bool,
+    tag_filter: Option<String>,
+    /// Recently used providers other than the current one, newest first
+    recent: Vec<String>,
+    /// Fuzzy filter on name, URL, model and tags; empty shows everything
+    search: String,
+    /// Provider whose full token the detail pane shows, and until when
+    revealed: Option<(String, Instant)>,
+    /// Names of the listed providers in display order; `list_state` indexes into this.
+    /// Rebuilt by `refresh_view` whenever the providers, filter or search change.
+    view: Vec<String>,
+    /// Changes that can be undone, latest last
+    undo_stack: Vec<Undo>,
+    /// Latest probe result per provider name
+    health: HashMap<String, Health>,
+    /// Probe threads report back here, tagged with the round that started them
+    health_tx: Sender<(u64, String, Health)>,
+    health_rx: Receiver<(u64, String, Health)>,
+    /// Current round of probes; results from earlier rounds are dropped
+    probe_round: u64,
+    /// Model lists loaded in the background, tagged with the request that asked for them
+    models_tx: Sender<(u64, Result<ModelList>)>,
+    models_rx: Receiver<(u64, Result<ModelList>)>,
+    /// Latest model request; answers to earlier ones are dropped
+    models_request: u64,
+    /// Whether the last frame used the compact layout for short terminals
+    compact: bool,
+    /// Where the last frame drew the list rows and how tall each item was, for mouse clicks
+    list_area: Rect,
+    item_heights: Vec<u16>,
+    /// Item clicked last and when, to spot double clicks
+    last_click: Option<(usize, Instant)>,
+    keys: KeyMap,
+    /// Whether the key overlay for the current mode is open
+    show_help: bool,
+    /// File to leave the shell commands in, when launched through the shell wrapper
+    handoff: Option<PathBuf>,
+    /// Whether the provider variables changed, so the launching shell needs new ones
+    env_changed: bool,
 }
 
-impl TuiApp {
-    pub fn new(config: Config) -> Self {
-        let mut list_state = ListState::default();
-        if !config.providers.is_empty() {
-            list_state.select(Some(0));
-        }
+/// Number of recent providers offered on the number keys
+const RECENT_SHOWN: usize = 3;
 
-        Self {
+/// Changes remembered for undo; older ones can still be restored with `cce restore`
+const UNDO_LIMIT: usize = 20;
+
+/// Terminals shorter than this get one line per provider and no panel borders
+const COMPACT_HEIGHT: u16 = 30;
+
+/// Terminals narrower than this leave out the detail pane
+const DETAIL_MIN_WIDTH: u16 = 100;
+
+/// Second click on the same provider within this time uses it
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// How long `r` shows the full token
+const REVEAL_DURATION: Duration = Duration::from_secs(5);
+
+impl TuiApp {
+    pub fn new(config: Config, keys: KeyMap) -> Self {
+        let (health_tx, health_rx) = mpsc::channel();
+        let (models_tx, models_rx) = mpsc::channel();
+        let mut app = Self {
             config,
-            list_state,
+            list_state: ListState::default(),
             input_mode: InputMode::Normal,
             message: None,
             message_is_error: false,
+            tag_filter: None,
+            recent: Vec::new(),
+            search: String::new(),
+            revealed: None,
+            view: Vec::new(),
+            undo_stack: Vec::new(),
+            health: HashMap::new(),
+            health_tx,
+            health_rx,
+            probe_round: 0,
+            models_tx,
+            models_rx,
+            models_request: 0,
+            compact: false,
+            list_area: Rect::default(),
+            item_heights: Vec::new(),
+            last_click: None,
+            keys,
+            show_help: false,
+            handoff: std::env::var_os(ENV_TUI_HANDOFF).map(PathBuf::from),
+            env_changed: false,
+        };
+        app.refresh_view();
+        app.refresh_recent();
+        app
+    }
+
+    /// Reload the recent providers from the switch history
+    fn refresh_recent(&mut self) {
+        let entries = history::load();
+        self.recent = history::recent_providers(&entries)
+            .into_iter()
+            .filter(|name| {
+                self.config.providers.contains_key(*name)
+                    && self.config.current_provider.as_deref() != Some(*name)
+            })
+            .take(RECENT_SHOWN)
+            .map(str::to_string)
+            .collect();
+    }
+
+    /// Rebuild the list order and keep the same provider selected where it is still listed.
+    /// When it is gone, e.g. after a delete, the row at the same position is selected.
+    fn refresh_view(&mut self) {
+        let selected_name = self.get_selected_provider().map(|p| p.name.clone());
+        self.view = self
+            .visible_providers()
+            .into_iter()
+            .map(|provider| provider.name.clone())
+            .collect();
+
+        let selected = match selected_name.and_then(|name| self.position_of(&name)) {
+            Some(index) => Some(index),
+            None if self.view.is_empty() => None,
+            None => Some(
+                self.list_state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(self.view.len() - 1),
+            ),
+        };
+        self.list_state.select(selected);
+    }
+
+    /// Rebuild the list and select `name`, if it is listed
+    fn select_name(&mut self, name: &str) {
+        self.refresh_view();
+        if let Some(index) = self.position_of(name) {
+            self.list_state.select(Some(index));
         }
     }
 
+    fn position_of(&self, name: &str) -> Option<usize> {
+        self.view.iter().position(|listed| listed == name)
+    }
+
+    /// Providers to list, honoring the tag filter; sorted by name, or best match first
+    /// while searching
+    fn visible_providers(&self) -> Vec<&Provider> {
+        let providers = self
+            .config
+            .providers
+            .values()
+            .filter(|provider| match &self.tag_filter {
+                Some(tag) => provider.has_tag(tag),
+                None => true,
+            });
+        if self.search.is_empty() {
+            let mut providers: Vec<&Provider> = providers.collect();
+            providers.sort_by(|a, b| a.name.cmp(&b.name));
+            return providers;
+        }
+
+        let mut scored: Vec<(i64, &Provider)> = providers
+            .filter_map(|provider| {
+                let resolved = self
+                    .config
+                    .resolve_provider(&provider.name)
+                    .unwrap_or_else(|_| provider.clone());
+                search_score(&resolved, &self.search).map(|score| (score, provider))
+            })
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.name.cmp(&b.name))
+        });
+        scored.into_iter().map(|(_, provider)| provider).collect()
+    }
+
+    /// Select the best match after the search changed
+    fn reset_selection(&mut self) {
+        self.refresh_view();
+        let selected = (!self.view.is_empty()).then_some(0);
+        self.list_state.select(selected);
+    }
+
+    /// Step the tag filter through every tag in use, then back to showing all
+    fn cycle_tag_filter(&mut self) {
+        let tags = self.config.all_tags();
+        self.tag_filter = match &self.tag_filter {
+            None => tags.first().cloned(),
+            Some(current) => tags
+                .iter()
+                .position(|tag| tag == current)
+                .and_then(|i| tags.get(i + 1))
+                .cloned(),
+        };
+
+        self.refresh_view();
+
+        self.message = Some(match &self.tag_filter {
+            Some(tag) => format!("Showing providers tagged '{}'", tag),
+            None => "Showing all providers".to_string(),
+        });
+        self.message_is_error = false;
+    }
+
     fn next(&mut self) {
-        if self.config.providers.is_empty() {
+        let count = self.view.len();
+        if count == 0 {
             return;
         }
         let i = match self.list_state.selected() {
             Some(i) => {
-                if i >= self.config.providers.len() - 1 {
+                if i >= count - 1 {
                     0
                 } else {
                     i + 1
@@ -73,13 +387,14 @@ impl TuiApp {
     }
 
     fn previous(&mut self) {
-        if self.config.providers.is_empty() {
+        let count = self.view.len();
+        if count == 0 {
             return;
         }
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.config.providers.len() - 1
+                    count - 1
                 } else {
                     i - 1
                 }
@@ -89,44 +404,211 @@ impl TuiApp {
         self.list_state.select(Some(i));
     }
 
-    fn get_selected_provider(&self) -> Option<&Provider> {
-        if let Some(selected) = self.list_state.selected() {
-            self.config.providers.values().nth(selected)
+    /// Move the selection for the mouse wheel, stopping at either end
+    fn scroll(&mut self, down: bool) {
+        let Some(last) = self.view.len().checked_sub(1) else {
+            return;
+        };
+        let i = self.list_state.selected().unwrap_or(0);
+        let i = if down {
+            (i + 1).min(last)
         } else {
-            None
+            i.saturating_sub(1)
+        };
+        self.list_state.select(Some(i));
+    }
+
+    /// Index of the list item drawn at a screen position
+    fn item_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        if column < area.x || column >= area.right() || row < area.y || row >= area.bottom() {
+            return None;
+        }
+        let mut top = area.y;
+        for (index, height) in self
+            .item_heights
+            .iter()
+            .enumerate()
+            .skip(self.list_state.offset())
+        {
+            top += height;
+            if row < top {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    /// Click selects a provider, double click uses it, the wheel moves the selection
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
+        if self.show_help || !matches!(self.input_mode, InputMode::Normal | InputMode::Search) {
+            return Ok(());
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollDown => self.scroll(true),
+            MouseEventKind::ScrollUp => self.scroll(false),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(index) = self.item_at(mouse.column, mouse.row) else {
+                    return Ok(());
+                };
+                let double = self
+                    .last_click
+                    .is_some_and(|(last, at)| last == index && at.elapsed() < DOUBLE_CLICK);
+                self.list_state.select(Some(index));
+                if double {
+                    self.last_click = None;
+                    self.use_provider()?;
+                } else {
+                    self.last_click = Some((index, Instant::now()));
+                }
+            }
+            _ => {}
         }
+        Ok(())
+    }
+
+    fn get_selected_provider(&self) -> Option<&Provider> {
+        let name = self.view.get(self.list_state.selected()?)?;
+        self.config.providers.get(name)
     }
 
     fn use_provider(&mut self) -> Result<()> {
-        if let Some(provider) = self.get_selected_provider() {
-            let name = provider.name.clone();
-            let token = provider.token.clone();
-            let api_url = provider.api_url.clone();
-            let model = provider.model.clone();
+        match self.get_selected_provider().map(|p| p.name.clone()) {
+            Some(name) => self.switch_to(&name),
+            None => Ok(()),
+        }
+    }
 
-            self.config.set_current_provider(&name);
-            self.config.save()?;
+    fn switch_to(&mut self, name: &str) -> Result<()> {
+        let provider = match self.config.resolve_provider(name) {
+            Ok(provider) => provider,
+            Err(err) => {
+                self.message = Some(err.to_string());
+                self.message_is_error = true;
+                return Ok(());
+            }
+        };
+        if !provider.has_token() {
+            self.message = Some(format!(
+                "Provider '{}' has no token, run 'cce use {}' to set it",
+                name, name
+            ));
+            self.message_is_error = true;
+            return Ok(());
+        }
 
-            // Set environment variables
-            let provider = Provider {
-                name: name.clone(),
-                api_url: api_url.clone(),
-                token: token.clone(),
-                model: model.clone(),
-            };
-            set_provider_env_vars(&provider);
+        let previous = self.config.current_provider.clone();
+        self.config.set_current_provider(name);
+        self.config.save()?;
+        // Saved for new shells as well, like `cce use`, so it is global
+        let recorded = history::record(name, Scope::Global);
+        if previous.as_deref() != Some(name) {
+            self.push_undo(Undo::Current(previous));
+        }
+        self.refresh_recent();
 
-            self.message = Some(format!("Switched to provider '{}'", name));
-            self.message_is_error = false;
+        self.export_env(&provider);
+
+        self.message = Some(match recorded {
+            Ok(()) => format!("Switched to provider '{}'", name),
+            Err(err) => format!(
+                "Switched to provider '{}', but could not record it in the history: {:#}",
+                name, err
+            ),
+        });
+        self.message_is_error = false;
+        Ok(())
+    }
+
+    fn export_env(&mut self, provider: &Provider) {
+        set_provider_env_vars(provider);
+        self.env_changed = true;
+    }
+
+    fn clear_env(&mut self) {
+        clear_all_env_vars();
+        self.env_changed = true;
+    }
+
+    /// Leave the commands for the final provider where the shell wrapper evals them on exit,
+    /// since the variables set here die with the TUI
+    fn hand_off(&self) -> Result<()> {
+        if !self.env_changed {
+            return Ok(());
         }
+        let Some(path) = &self.handoff else {
+            eprintln!(
+                "{} The variables in this shell are unchanged; with the shell integration \
+                 (`cce install`), `cce tui` updates them on exit",
+                theme::INFO
+            );
+            return Ok(());
+        };
+
+        let commands = match &self.config.current_provider {
+            Some(name) => generate_export_commands(&self.config.resolve_provider(name)?),
+            None => generate_unset_commands(),
+        };
+        fs::write(path, commands + "\n")
+            .with_context(|| format!("Failed to write shell commands to {:?}", path))
+    }
+
+    /// Show the selected provider's full token in the detail pane for a few seconds
+    fn reveal_token(&mut self) {
+        if let Some(name) = self.get_selected_provider().map(|p| p.name.clone()) {
+            self.revealed = Some((name, Instant::now() + REVEAL_DURATION));
+        }
+    }
+
+    /// Whether the full token of `name` is currently shown
+    fn is_revealed(&self, name: &str) -> bool {
+        self.revealed
+            .as_ref()
+            .is_some_and(|(revealed, until)| revealed == name && Instant::now() < *until)
+    }
+
+    fn copy_selected(&mut self, target: CopyTarget) -> Result<()> {
+        let Some(name) = self.get_selected_provider().map(|p| p.name.clone()) else {
+            return Ok(());
+        };
+        let provider = match self.config.resolve_provider(&name) {
+            Ok(provider) => provider,
+            Err(err) => {
+                self.message = Some(err.to_string());
+                self.message_is_error = true;
+                return Ok(());
+            }
+        };
+
+        let (label, text) = match target {
+            CopyTarget::Token if !provider.has_token() => {
+                self.message = Some(format!("Provider '{}' has no token to copy", name));
+                self.message_is_error = true;
+                return Ok(());
+            }
+            CopyTarget::Token => ("token", provider.token.clone()),
+            CopyTarget::Url => ("URL", provider.api_url.clone()),
+            CopyTarget::Exports => ("export commands", generate_export_commands(&provider)),
+        };
+        clipboard::copy(&text)?;
+
+        self.message = Some(format!(
+            "Copied the {} of '{}' to the clipboard",
+            label, name
+        ));
+        self.message_is_error = false;
         Ok(())
     }
 
     fn clear_provider(&mut self) -> Result<()> {
+        let previous = self.config.current_provider.clone();
         self.config.clear_current_provider();
         self.config.save()?;
+        if previous.is_some() {
+            self.push_undo(Undo::Current(previous));
+        }
 
-        clear_all_env_vars();
+        self.clear_env();
 
         self.message = Some("Cleared current provider".to_string());
         self.message_is_error = false;
@@ -134,127 +616,632 @@ impl TuiApp {
     }
 
     fn delete_provider(&mut self) -> Result<()> {
-        if let Some(provider) = self.get_selected_provider() {
+        if let Some(provider) = self.get_selected_provider().cloned() {
             let name = provider.name.clone();
+            let dependents = self.config.dependents(&name);
+            if !dependents.is_empty() {
+                self.message = Some(format!(
+                    "'{}' is extended by {}, delete those first or use 'cce delete --force'",
+                    name,
+                    dependents.join(", ")
+                ));
+                self.message_is_error = true;
+                return Ok(());
+            }
+            let was_current = self.config.current_provider.as_ref() == Some(&name);
+            let default_of: Vec<String> = self
+                .config
+                .groups
+                .iter()
+                .filter(|(_, group)| group.default.as_deref() == Some(name.as_str()))
+                .map(|(group, _)| group.clone())
+                .collect();
+            // Also kept on disk, so the provider survives closing the TUI
+            trash::put(provider.clone())?;
             self.config.remove_provider(&name);
             self.config.save()?;
+            self.health.remove(&name);
+            self.refresh_view();
+            self.push_undo(Undo::Delete {
+                provider: Box::new(provider),
+                was_current,
+                default_of,
+            });
+
+            self.message = Some(format!(
+                "Deleted provider '{}', press Ctrl-Z or U to undo",
+                name
+            ));
+            self.message_is_error = false;
+        }
+        Ok(())
+    }
+
+    /// Probe every provider's gateway in background threads.
+    /// Results come in through `collect_health` while the UI keeps running.
+    fn start_probes(&mut self) {
+        self.probe_round += 1;
+        let providers = &self.config.providers;
+        self.health.retain(|name, _| providers.contains_key(name));
 
-            // Adjust selection
-            if self.config.providers.is_empty() {
-                self.list_state.select(None);
-            } else if let Some(selected) = self.list_state.selected() {
-                if selected >= self.config.providers.len() {
-                    self.list_state
-                        .select(Some(self.config.providers.len() - 1));
+        let names: Vec<String> = self.config.providers.keys().cloned().collect();
+        for name in names {
+            // Earlier results stay up until the new ones replace them
+            let checked = self.health.contains_key(&name);
+            self.start_probe(name, checked);
+        }
+
+        self.message = Some(format!(
+            "Checking {} providers...",
+            self.config.providers.len()
+        ));
+        self.message_is_error = false;
+    }
+
+    /// Probe one provider as part of the current round, e.g. one just added, showing it
+    /// as being checked unless `keep_result` leaves its last result up meanwhile
+    fn start_probe(&mut self, name: String, keep_result: bool) {
+        let provider = match self.config.resolve_provider(&name) {
+            Ok(provider) => provider,
+            Err(err) => {
+                self.health.insert(name, Health::Down(err.to_string()));
+                return;
+            }
+        };
+        if !keep_result {
+            self.health.insert(name.clone(), Health::Checking);
+        }
+
+        let health_tx = self.health_tx.clone();
+        let round = self.probe_round;
+        thread::spawn(move || {
+            // The TUI may have quit in the meantime, nobody is listening then
+            let _ = health_tx.send((round, name, health::probe(&provider)));
+        });
+    }
+
+    /// Take in the probes that finished since the last call, without waiting for the rest
+    fn collect_health(&mut self) {
+        while let Ok((round, name, health)) = self.health_rx.try_recv() {
+            if round == self.probe_round {
+                self.health.insert(name, health);
+            }
+        }
+    }
+
+    /// Ask the gateway for its models on another thread, so a slow one does not block the UI
+    fn start_model_load(&mut self, provider: Provider) {
+        self.models_request += 1;
+        let models_tx = self.models_tx.clone();
+        let request = self.models_request;
+        thread::spawn(move || {
+            let _ = models_tx.send((request, models::load_models(&provider, false)));
+        });
+
+        self.message = Some("Loading models...".to_string());
+        self.message_is_error = false;
+    }
+
+    /// Open the picker for a model list that arrived, if it is still wanted
+    fn collect_models(&mut self) {
+        while let Ok((request, result)) = self.models_rx.try_recv() {
+            if request == self.models_request {
+                self.show_models(result);
+            }
+        }
+    }
+
+    fn push_undo(&mut self, undo: Undo) {
+        self.undo_stack.push(undo);
+        let skip = self.undo_stack.len().saturating_sub(UNDO_LIMIT);
+        self.undo_stack.drain(..skip);
+    }
+
+    /// Take back the latest delete, switch or clear
+    fn undo(&mut self) -> Result<()> {
+        match self.undo_stack.pop() {
+            None => {
+                self.message = Some("Nothing to undo".to_string());
+                self.message_is_error = false;
+            }
+            Some(Undo::Delete {
+                provider,
+                was_current,
+                default_of,
+            }) => {
+                let name = provider.name.clone();
+                if self.config.providers.contains_key(&name) {
+                    self.message = Some(format!(
+                        "Cannot restore '{}', a provider with that name exists",
+                        name
+                    ));
+                    self.message_is_error = true;
+                    return Ok(());
                 }
+
+                self.config.providers.insert(name.clone(), *provider);
+                // Unless another default was picked in the meantime
+                for group in default_of {
+                    if let Some(group) = self.config.groups.get_mut(&group) {
+                        group.default.get_or_insert_with(|| name.clone());
+                    }
+                }
+                if was_current {
+                    self.config.set_current_provider(&name);
+                    if let Ok(provider) = self.config.resolve_provider(&name) {
+                        self.export_env(&provider);
+                    }
+                }
+                self.config.save()?;
+                trash::remove(&name)?;
+                self.start_probe(name.clone(), false);
+                self.select_name(&name);
+                self.refresh_recent();
+
+                self.message = Some(format!("Restored provider '{}'", name));
+                self.message_is_error = false;
             }
+            Some(Undo::Current(previous)) => {
+                match &previous {
+                    Some(name) => {
+                        let provider = match self.config.resolve_provider(name) {
+                            Ok(provider) => provider,
+                            Err(err) => {
+                                self.message = Some(format!("Cannot switch back: {}", err));
+                                self.message_is_error = true;
+                                return Ok(());
+                            }
+                        };
+                        self.config.set_current_provider(name);
+                        self.export_env(&provider);
+                    }
+                    None => {
+                        self.config.clear_current_provider();
+                        self.clear_env();
+                    }
+                }
+                self.config.save()?;
+                self.refresh_recent();
 
-            self.message = Some(format!("Deleted provider '{}'", name));
-            self.message_is_error = false;
+                self.message = Some(match previous {
+                    Some(name) => format!("Switched back to provider '{}'", name),
+                    None => "Current provider cleared again".to_string(),
+                });
+                self.message_is_error = false;
+            }
         }
         Ok(())
     }
 
     fn save_new_provider(&mut self) -> Result<()> {
-        if let InputMode::AddProvider(state) = &self.input_mode {
-            if state.name.is_empty() || state.url.is_empty() || state.token.is_empty() {
-                self.message = Some("Name, URL, and Token are required".to_string());
+        let InputMode::AddProvider(state) = &mut self.input_mode else {
+            return Ok(());
+        };
+        // Errors show up under their fields, so jump to the first one
+        if let Some(field) =
+            (0..ADD_FORM_FIELDS).find(|&field| state.field_error(field, &self.config).is_some())
+        {
+            state.submitted = true;
+            state.current_field = field;
+            self.message = None;
+            return Ok(());
+        }
+
+        let name = state.name.as_str().trim().to_string();
+        let model = state.model.as_str().trim();
+        let model = if model.is_empty() {
+            None
+        } else {
+            Some(model.to_string())
+        };
+
+        self.config.add_provider(
+            name.clone(),
+            state.url.as_str().trim().to_string(),
+            state.token.as_str().trim().to_string(),
+            model,
+        );
+        if let (Some(preset), Some(provider)) = (state.preset, self.config.providers.get_mut(&name))
+        {
+            preset.apply(provider);
+        }
+        self.config.save()?;
+        self.input_mode = InputMode::Normal;
+
+        self.start_probe(name.clone(), false);
+        self.select_name(&name);
+
+        self.message = Some(format!("Added provider '{}'", name));
+        self.message_is_error = false;
+        Ok(())
+    }
+
+    /// Insert pasted text into the field being edited
+    fn handle_paste(&mut self, text: &str) {
+        match &mut self.input_mode {
+            InputMode::AddProvider(state) if state.model_picker.is_none() => {
+                state.current_field_mut().insert_str(text);
+            }
+            InputMode::Search => {
+                self.search.extend(text.chars().filter(|c| !c.is_control()));
+                self.reset_selection();
+            }
+            InputMode::ModelInput { input, .. } => input.insert_str(text),
+            _ => {}
+        }
+    }
+
+    /// Open the model picker for the selected provider
+    fn open_provider_model_picker(&mut self) {
+        let Some(name) = self.get_selected_provider().map(|p| p.name.clone()) else {
+            return;
+        };
+        let provider = match self.config.resolve_provider(&name) {
+            Ok(provider) => provider,
+            Err(err) => {
+                self.message = Some(err.to_string());
                 self.message_is_error = true;
-                return Ok(());
+                return;
             }
+        };
 
-            let model = if state.model.is_empty() {
-                None
-            } else {
-                Some(state.model.clone())
-            };
+        self.input_mode = InputMode::LoadingModels {
+            provider: name,
+            current: provider.model.clone(),
+        };
+        self.start_model_load(provider);
+    }
 
-            self.config.add_provider(
-                state.name.clone(),
-                state.url.clone(),
-                state.token.clone(),
-                model,
-            );
-            self.config.save()?;
+    /// Hand a loaded model list to whatever asked for it: the model picker or the add form
+    fn show_models(&mut self, result: Result<ModelList>) {
+        match std::mem::replace(&mut self.input_mode, InputMode::Normal) {
+            InputMode::LoadingModels { provider, current } => match result {
+                Ok(list) if list.models.is_empty() => self.open_model_input(
+                    provider,
+                    current,
+                    "The gateway did not report any models",
+                ),
+                Ok(list) => {
+                    match list.stale_reason {
+                        Some(reason) => {
+                            self.message = Some(format!("Showing cached models: {}", reason));
+                            self.message_is_error = true;
+                        }
+                        None => self.message = None,
+                    }
+                    let selected = list
+                        .models
+                        .iter()
+                        .position(|model| Some(model) == current.as_ref())
+                        .unwrap_or(0);
+                    self.input_mode = InputMode::ModelPicker {
+                        provider,
+                        models: list.models,
+                        selected,
+                    };
+                }
+                Err(err) => self.open_model_input(
+                    provider,
+                    current,
+                    &format!("Could not load models: {}", err),
+                ),
+            },
+            InputMode::AddProvider(mut state) if state.loading_models => {
+                state.loading_models = false;
+                match result {
+                    Ok(list) if list.models.is_empty() => {
+                        self.message = Some("The gateway did not report any models".to_string());
+                        self.message_is_error = true;
+                    }
+                    Ok(list) => {
+                        match list.stale_reason {
+                            Some(reason) => {
+                                self.message = Some(format!("Showing cached models: {}", reason));
+                                self.message_is_error = true;
+                            }
+                            None => {
+                                self.message = Some(format!("Found {} models", list.models.len()));
+                                self.message_is_error = false;
+                            }
+                        }
+                        let selected = list
+                            .models
+                            .iter()
+                            .position(|model| model == state.model.as_str())
+                            .unwrap_or(0);
+                        state.model_picker = Some((list.models, selected));
+                    }
+                    Err(err) => {
+                        self.message = Some(format!("Could not load models: {}", err));
+                        self.message_is_error = true;
+                    }
+                }
+                self.input_mode = InputMode::AddProvider(state);
+            }
+            // The request was cancelled or the user moved on
+            mode => self.input_mode = mode,
+        }
+    }
 
-            // Select the newly added provider
-            let index = self.config.providers.len() - 1;
-            self.list_state.select(Some(index));
+    /// Ask for the model as text, for gateways without a usable `/v1/models`
+    fn open_model_input(&mut self, provider: String, current: Option<String>, reason: &str) {
+        self.message = Some(format!("{}, type the model name instead", reason));
+        self.message_is_error = true;
+        self.input_mode = InputMode::ModelInput {
+            provider,
+            input: LineInput::new(current.unwrap_or_default()),
+        };
+    }
 
-            self.message = Some(format!("Added provider '{}'", state.name));
-            self.message_is_error = false;
+    /// Store `model` on `name`, re-exporting the variables if it is the current provider
+    fn set_provider_model(&mut self, name: &str, model: String) -> Result<()> {
+        // Like `cce model`, the new model replaces any per-tier ones
+        if let Some(provider) = self.config.providers.get_mut(name) {
+            provider.model = Some(model.clone());
+            provider.clear_tier_models();
         }
+        self.config.save()?;
+        // The model is searchable, so the order may change while filtering
+        self.refresh_view();
 
-        self.input_mode = InputMode::Normal;
+        if self.config.current_provider.as_deref() == Some(name) {
+            if let Ok(provider) = self.config.resolve_provider(name) {
+                self.export_env(&provider);
+            }
+        }
+
+        self.message = Some(format!("Model for '{}' set to '{}'", name, model));
+        self.message_is_error = false;
         Ok(())
     }
 
+    /// Load the gateway's models for the add form and open the picker over it
+    fn open_model_picker(&mut self) {
+        let InputMode::AddProvider(state) = &mut self.input_mode else {
+            return;
+        };
+
+        let mut provider = Provider {
+            name: state.name.as_str().to_string(),
+            api_url: state.url.as_str().trim().to_string(),
+            token: state.token.as_str().trim().to_string(),
+            ..Default::default()
+        };
+        if let Some(preset) = state.preset {
+            preset.apply(&mut provider);
+        }
+        if provider.api_url.is_empty() {
+            self.message = Some("Enter the API URL before picking a model".to_string());
+            self.message_is_error = true;
+            return;
+        }
+
+        // The form stays editable while the models load
+        state.loading_models = true;
+        self.start_model_load(provider);
+    }
+
+    /// Whether plain characters type into a field in the current mode
+    fn accepts_text(&self) -> bool {
+        match &self.input_mode {
+            InputMode::Search | InputMode::ModelInput { .. } => true,
+            InputMode::AddProvider(state) => state.model_picker.is_none(),
+            _ => false,
+        }
+    }
+
+    /// Whether a key moves down (`Some(true)`) or up in a list; arrows always do
+    fn list_move(&self, key: KeyCode, modifiers: KeyModifiers) -> Option<bool> {
+        // While typing, letters bound to moving are text
+        let bindable = !(self.accepts_text() && KeyBinding::is_text(key, modifiers));
+        if key == KeyCode::Down || bindable && self.keys.matches(Action::Down, key, modifiers) {
+            Some(true)
+        } else if key == KeyCode::Up || bindable && self.keys.matches(Action::Up, key, modifiers) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
     fn handle_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<bool> {
+        // Any key closes the overlay
+        if self.show_help {
+            self.show_help = false;
+            return Ok(false);
+        }
+        if self.keys.matches(Action::Help, key, modifiers)
+            && !(self.accepts_text() && KeyBinding::is_text(key, modifiers))
+        {
+            self.show_help = true;
+            return Ok(false);
+        }
+        let list_move = self.list_move(key, modifiers);
+
         match &mut self.input_mode {
-            InputMode::Normal => match key {
-                KeyCode::Char('q') | KeyCode::Esc => return Ok(true),
-                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(true),
-                KeyCode::Down | KeyCode::Char('j') => self.next(),
-                KeyCode::Up | KeyCode::Char('k') => self.previous(),
-                KeyCode::Enter | KeyCode::Char('u') => self.use_provider()?,
-                KeyCode::Char('a') => {
-                    self.input_mode = InputMode::AddProvider(AddProviderState::default());
-                    self.message = None;
+            InputMode::Normal => {
+                if key == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                    return Ok(true);
+                }
+                if let KeyCode::Char(digit @ '1'..='9') = key {
+                    let index = digit as usize - '1' as usize;
+                    if let Some(name) = self.recent.get(index).cloned() {
+                        self.switch_to(&name)?;
+                    }
+                    return Ok(false);
                 }
-                KeyCode::Char('d') => {
-                    if self.get_selected_provider().is_some() {
+
+                match self.keys.action(key, modifiers) {
+                    Some(Action::Quit) => return Ok(true),
+                    Some(Action::Undo) => self.undo()?,
+                    Some(Action::Down) => self.next(),
+                    Some(Action::Up) => self.previous(),
+                    Some(Action::Use) => self.use_provider()?,
+                    Some(Action::Add) => {
+                        self.input_mode = InputMode::PresetPicker(0);
+                        self.message = None;
+                    }
+                    Some(Action::Delete) if self.get_selected_provider().is_some() => {
                         self.input_mode = InputMode::DeleteConfirm;
                         self.message = None;
                     }
+                    Some(Action::Clear) => self.clear_provider()?,
+                    Some(Action::FilterTag) => self.cycle_tag_filter(),
+                    Some(Action::Search) => {
+                        self.input_mode = InputMode::Search;
+                        self.message = None;
+                    }
+                    Some(Action::Model) => self.open_provider_model_picker(),
+                    Some(Action::Test) => self.start_probes(),
+                    Some(Action::Reveal) => self.reveal_token(),
+                    Some(Action::CopyToken) => self.copy_selected(CopyTarget::Token)?,
+                    Some(Action::CopyUrl) => self.copy_selected(CopyTarget::Url)?,
+                    Some(Action::CopyExports) => self.copy_selected(CopyTarget::Exports)?,
+                    Some(Action::Delete | Action::Help) | None => {}
+                }
+            }
+            InputMode::Search => match key {
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.search.clear();
+                    self.refresh_view();
+                }
+                KeyCode::Enter => {
+                    self.use_provider()?;
+                    self.input_mode = InputMode::Normal;
+                    self.search.clear();
+                    self.refresh_view();
+                }
+                _ if list_move == Some(true) => self.next(),
+                _ if list_move == Some(false) => self.previous(),
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.reset_selection();
+                }
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(true),
+                KeyCode::Char(c) if KeyBinding::is_text(key, modifiers) => {
+                    self.search.push(c);
+                    self.reset_selection();
                 }
-                KeyCode::Char('c') => self.clear_provider()?,
                 _ => {}
             },
-            InputMode::AddProvider(state) => match key {
+            InputMode::ModelPicker {
+                provider,
+                models,
+                selected,
+            } => match key {
                 KeyCode::Esc => {
                     self.input_mode = InputMode::Normal;
                     self.message = None;
                 }
-                KeyCode::Tab => {
-                    state.current_field = (state.current_field + 1) % 4;
+                _ if list_move == Some(true) => {
+                    *selected = (*selected + 1) % models.len();
                 }
-                KeyCode::BackTab => {
-                    state.current_field = if state.current_field == 0 {
-                        3
-                    } else {
-                        state.current_field - 1
-                    };
+                _ if list_move == Some(false) => {
+                    *selected = selected.checked_sub(1).unwrap_or(models.len() - 1);
                 }
                 KeyCode::Enter => {
-                    if state.current_field == 3 {
-                        self.save_new_provider()?;
+                    let name = std::mem::take(provider);
+                    let model = std::mem::take(&mut models[*selected]);
+                    self.input_mode = InputMode::Normal;
+                    self.set_provider_model(&name, model)?;
+                }
+                _ => {}
+            },
+            InputMode::LoadingModels { .. } => {
+                if key == KeyCode::Esc {
+                    // Drop the answer when it comes
+                    self.models_request += 1;
+                    self.input_mode = InputMode::Normal;
+                    self.message = None;
+                }
+            }
+            InputMode::ModelInput { provider, input } => match key {
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.message = None;
+                }
+                KeyCode::Enter => {
+                    let model = input.as_str().trim().to_string();
+                    if model.is_empty() {
+                        self.message = Some("Model cannot be empty".to_string());
+                        self.message_is_error = true;
                     } else {
-                        state.current_field += 1;
+                        let name = std::mem::take(provider);
+                        self.input_mode = InputMode::Normal;
+                        self.set_provider_model(&name, model)?;
                     }
                 }
-                KeyCode::Backspace => match state.current_field {
-                    0 => {
-                        state.name.pop();
-                    }
-                    1 => {
-                        state.url.pop();
+                _ => {
+                    input.handle_key(key, modifiers);
+                }
+            },
+            InputMode::PresetPicker(selected) => match key {
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.message = None;
+                }
+                _ if list_move == Some(true) => {
+                    *selected = (*selected + 1) % (PRESETS.len() + 1);
+                }
+                _ if list_move == Some(false) => {
+                    *selected = selected.checked_sub(1).unwrap_or(PRESETS.len());
+                }
+                KeyCode::Enter => {
+                    let state = match selected.checked_sub(1) {
+                        Some(i) => AddProviderState::from_preset(&PRESETS[i]),
+                        None => AddProviderState::default(),
+                    };
+                    self.input_mode = InputMode::AddProvider(state);
+                }
+                _ => {}
+            },
+            InputMode::AddProvider(state) if state.model_picker.is_some() => {
+                let Some((models, selected)) = &mut state.model_picker else {
+                    return Ok(false);
+                };
+                match key {
+                    KeyCode::Esc => state.model_picker = None,
+                    _ if list_move == Some(true) => {
+                        *selected = (*selected + 1) % models.len();
                     }
-                    2 => {
-                        state.token.pop();
+                    _ if list_move == Some(false) => {
+                        *selected = selected.checked_sub(1).unwrap_or(models.len() - 1);
                     }
-                    3 => {
-                        state.model.pop();
+                    KeyCode::Enter => {
+                        state.model = LineInput::new(models[*selected].clone());
+                        state.model_picker = None;
+                        state.current_field = 3;
                     }
                     _ => {}
-                },
-                KeyCode::Char(c) => match state.current_field {
-                    0 => state.name.push(c),
-                    1 => state.url.push(c),
-                    2 => state.token.push(c),
-                    3 => state.model.push(c),
-                    _ => {}
-                },
-                _ => {}
+                }
+            }
+            InputMode::AddProvider(state) => match key {
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.message = None;
+                }
+                KeyCode::Tab | KeyCode::Down => {
+                    state.current_field = (state.current_field + 1) % ADD_FORM_FIELDS;
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    state.current_field = state
+                        .current_field
+                        .checked_sub(1)
+                        .unwrap_or(ADD_FORM_FIELDS - 1);
+                }
+                KeyCode::Enter => {
+                    if state.current_field == ADD_FORM_FIELDS - 1 {
+                        self.save_new_provider()?;
+                    } else {
+                        state.current_field += 1;
+                    }
+                }
+                KeyCode::Char('l') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.open_model_picker();
+                }
+                _ => {
+                    state.current_field_mut().handle_key(key, modifiers);
+                }
             },
             InputMode::DeleteConfirm => match key {
                 KeyCode::Char('y') | KeyCode::Char('Y') => {
@@ -274,15 +1261,24 @@ impl TuiApp {
 }
 
 pub fn run_tui(config: Config) -> Result<()> {
+    // Report mistakes in [tui.keys] before taking over the terminal
+    let keys = KeyMap::from_config(&config.tui.keys)?;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = TuiApp::new(config);
+    let mut app = TuiApp::new(config, keys);
+    app.start_probes();
 
     // Run app
     let res = run_app(&mut terminal, &mut app);
@@ -292,38 +1288,49 @@ pub fn run_tui(config: Config) -> Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
     if let Err(err) = res {
         println!("Error: {:?}", err);
     }
+    app.hand_off()?;
 
     Ok(())
 }
 
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut TuiApp) -> Result<()> {
     loop {
+        app.collect_health();
+        app.collect_models();
         terminal.draw(|f| ui(f, app))?;
 
-        if let Event::Key(key) = event::read()? {
-            if app.handle_input(key.code, key.modifiers)? {
-                return Ok(());
-            }
+        // Wake up now and then to show finished probes and hide a revealed token on time
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        match event::read()? {
+            Event::Key(key) if app.handle_input(key.code, key.modifiers)? => return Ok(()),
+            Event::Paste(text) => app.handle_paste(&text),
+            Event::Mouse(mouse) => app.handle_mouse(mouse)?,
+            _ => {}
         }
     }
 }
 
 fn ui(f: &mut Frame, app: &mut TuiApp) {
+    app.compact = f.size().height < COMPACT_HEIGHT;
+    let (margin, bar) = if app.compact { (0, 1) } else { (2, 3) };
     let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints([
-            Constraint::Length(3),
-            Constraint::Min(10),
-            Constraint::Length(3),
-            Constraint::Length(3),
+        .direction(Direction::Vertical)
+        .margin(margin)
+        .constraints([
+            Constraint::Length(bar),
+            Constraint::Min(3),
+            Constraint::Length(bar),
+            Constraint::Length(bar),
         ])
         .split(f.size());
 
@@ -331,20 +1338,44 @@ fn ui(f: &mut Frame, app: &mut TuiApp) {
     let title = Paragraph::new("CCE - Claude Config Environment")
         .style(
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme::current().accent)
                 .add_modifier(Modifier::BOLD),
         )
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
+        .block(panel(app.compact, ""));
     f.render_widget(title, chunks[0]);
 
     // Main content area
     match &app.input_mode {
-        InputMode::Normal => {
+        InputMode::Normal | InputMode::Search => {
             render_provider_list(f, app, chunks[1]);
         }
+        InputMode::PresetPicker(selected) => {
+            render_preset_picker(f, *selected, chunks[1]);
+        }
         InputMode::AddProvider(state) => {
-            render_add_provider_form(f, state, chunks[1]);
+            render_add_provider_form(f, state, &app.config, chunks[1]);
+        }
+        InputMode::ModelPicker { .. } => {
+            render_provider_list(f, app, chunks[1]);
+            if let InputMode::ModelPicker {
+                models, selected, ..
+            } = &app.input_mode
+            {
+                render_model_picker(f, models, *selected, chunks[1]);
+            }
+        }
+        InputMode::LoadingModels { .. } => {
+            render_provider_list(f, app, chunks[1]);
+            if let InputMode::LoadingModels { provider, .. } = &app.input_mode {
+                render_models_loading(f, provider, chunks[1]);
+            }
+        }
+        InputMode::ModelInput { .. } => {
+            render_provider_list(f, app, chunks[1]);
+            if let InputMode::ModelInput { provider, input } = &app.input_mode {
+                render_model_input(f, provider, input, chunks[1]);
+            }
         }
         InputMode::DeleteConfirm => {
             render_provider_list(f, app, chunks[1]);
@@ -355,89 +1386,380 @@ fn ui(f: &mut Frame, app: &mut TuiApp) {
     // Status message
     if let Some(ref message) = app.message {
         let style = if app.message_is_error {
-            Style::default().fg(Color::Red)
+            Style::default().fg(theme::current().error)
         } else {
-            Style::default().fg(Color::Green)
+            Style::default().fg(theme::current().success)
         };
         let msg = Paragraph::new(message.as_str())
             .style(style)
             .alignment(Alignment::Center)
-            .block(Block::default().borders(Borders::ALL).title("Status"));
+            .block(panel(app.compact, "Status"));
         f.render_widget(msg, chunks[2]);
     } else {
-        let msg = Paragraph::new("").block(Block::default().borders(Borders::ALL).title("Status"));
+        let msg = Paragraph::new("").block(panel(app.compact, "Status"));
         f.render_widget(msg, chunks[2]);
     }
 
-    // Help text
-    let help_text = match &app.input_mode {
-        InputMode::Normal => {
-            "↑/↓: Navigate | Enter/u: Use Provider | a: Add | d: Delete | c: Clear | q/Esc: Quit"
+    // Help text, the essentials only; the overlay has the rest
+    let keys = &app.keys;
+    let mut help_text = match &app.input_mode {
+        InputMode::Normal => format!(
+            "{}: Use | {}: Search | {}: Add | {}: Delete | {}: Quit",
+            keys.keys(Action::Use),
+            keys.keys(Action::Search),
+            keys.keys(Action::Add),
+            keys.keys(Action::Delete),
+            keys.keys(Action::Quit)
+        ),
+        InputMode::Search => "Type to filter | Enter: Use | Esc: Clear Filter".to_string(),
+        InputMode::PresetPicker(_) => "Enter: Choose Preset | Esc: Cancel".to_string(),
+        InputMode::AddProvider(state) if state.model_picker.is_some() => {
+            "Enter: Choose Model | Esc: Back to Form".to_string()
+        }
+        InputMode::AddProvider(_) => {
+            "Tab/Shift+Tab: Next/Prev Field | Ctrl+L: Pick Model | Enter: Save | Esc: Cancel"
+                .to_string()
+        }
+        InputMode::LoadingModels { .. } => "Esc: Cancel".to_string(),
+        InputMode::ModelPicker { .. } | InputMode::ModelInput { .. } => {
+            "Enter: Set Model | Esc: Cancel".to_string()
         }
-        InputMode::AddProvider(_) => "Tab/Shift+Tab: Next/Prev Field | Enter: Save | Esc: Cancel",
-        InputMode::DeleteConfirm => "y: Confirm Delete | n/Esc: Cancel",
+        InputMode::DeleteConfirm => "y: Confirm Delete | n/Esc: Cancel".to_string(),
     };
+    let help_keys = if app.accepts_text() {
+        keys.command_keys(Action::Help)
+    } else {
+        keys.keys(Action::Help)
+    };
+    if !help_keys.is_empty() {
+        help_text.push_str(&format!(" | {}: All Keys", help_keys));
+    }
     let help = Paragraph::new(help_text)
-        .style(Style::default().fg(Color::Yellow))
+        .style(Style::default().fg(theme::current().warning))
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL).title("Help"));
+        .block(panel(app.compact, "Help"));
     f.render_widget(help, chunks[3]);
+
+    if app.show_help {
+        render_key_overlay(f, app);
+    }
+}
+
+/// Keys for moving through a list; the arrows work there whatever the bindings
+fn list_keys(app: &TuiApp, action: Action, arrow: &str) -> String {
+    let bound = if app.accepts_text() {
+        app.keys.command_keys(action)
+    } else {
+        app.keys.keys(action)
+    };
+    if bound.split('/').any(|key| key == arrow) {
+        bound
+    } else if bound.is_empty() {
+        arrow.to_string()
+    } else {
+        format!("{}/{}", arrow, bound)
+    }
+}
+
+/// Every key of the current mode as (keys, what they do)
+fn key_help(app: &TuiApp) -> Vec<(String, &'static str)> {
+    let navigation = || {
+        vec![
+            (
+                list_keys(app, Action::Up, theme::ARROW_UP.as_str()),
+                "Move up",
+            ),
+            (
+                list_keys(app, Action::Down, theme::ARROW_DOWN.as_str()),
+                "Move down",
+            ),
+        ]
+    };
+    let mut entries = match &app.input_mode {
+        InputMode::Normal => {
+            let mut entries = Vec::new();
+            for action in Action::ALL {
+                let keys = app.keys.keys(action);
+                if !keys.is_empty() {
+                    entries.push((keys, action.description()));
+                }
+                if action == Action::Use {
+                    entries.push(("1-9".to_string(), "Use a recent provider"));
+                }
+            }
+            entries.extend([
+                ("Ctrl+C".to_string(), "Quit"),
+                ("Click".to_string(), "Select a provider"),
+                ("Double-click".to_string(), "Use a provider"),
+                ("Wheel".to_string(), "Move the selection"),
+            ]);
+            return entries;
+        }
+        InputMode::Search => {
+            let mut entries = vec![("Type".to_string(), "Filter by name, URL, model and tags")];
+            entries.extend(navigation());
+            entries.extend([
+                ("Backspace".to_string(), "Delete the last character"),
+                ("Enter".to_string(), "Use the selected provider"),
+                ("Esc".to_string(), "Clear the filter"),
+            ]);
+            entries
+        }
+        InputMode::PresetPicker(_) => {
+            let mut entries = navigation();
+            entries.extend([
+                ("Enter".to_string(), "Fill the form from the preset"),
+                ("Esc".to_string(), "Cancel"),
+            ]);
+            entries
+        }
+        InputMode::AddProvider(state) if state.model_picker.is_some() => {
+            let mut entries = navigation();
+            entries.extend([
+                ("Enter".to_string(), "Use the model"),
+                ("Esc".to_string(), "Back to the form"),
+            ]);
+            entries
+        }
+        InputMode::AddProvider(_) => [
+            (format!("Tab/{}", theme::ARROW_DOWN), "Next field"),
+            (format!("Shift+Tab/{}", theme::ARROW_UP), "Previous field"),
+            (
+                format!("{}/{}", theme::ARROW_LEFT, theme::ARROW_RIGHT),
+                "Move the cursor",
+            ),
+            (
+                "Home/End, Ctrl+A/Ctrl+E".to_string(),
+                "Jump to the start or end",
+            ),
+            ("Backspace/Del".to_string(), "Delete a character"),
+            ("Ctrl+W".to_string(), "Delete the word before the cursor"),
+            ("Ctrl+U/Ctrl+K".to_string(), "Delete to the start or end"),
+            ("Ctrl+L".to_string(), "Pick the model from the gateway"),
+            ("Enter".to_string(), "Next field, or save on the last one"),
+            ("Esc".to_string(), "Cancel"),
+        ]
+        .into(),
+        InputMode::ModelPicker { .. } => {
+            let mut entries = navigation();
+            entries.extend([
+                ("Enter".to_string(), "Set the model"),
+                ("Esc".to_string(), "Cancel"),
+            ]);
+            entries
+        }
+        InputMode::LoadingModels { .. } => vec![("Esc".to_string(), "Cancel")],
+        InputMode::ModelInput { .. } => vec![
+            (
+                format!("{}/{}", theme::ARROW_LEFT, theme::ARROW_RIGHT),
+                "Move the cursor",
+            ),
+            ("Ctrl+W".to_string(), "Delete the word before the cursor"),
+            ("Ctrl+U/Ctrl+K".to_string(), "Delete to the start or end"),
+            ("Enter".to_string(), "Set the model"),
+            ("Esc".to_string(), "Cancel"),
+        ],
+        InputMode::DeleteConfirm => vec![
+            ("y".to_string(), "Delete the provider"),
+            ("n/Esc".to_string(), "Keep it"),
+        ],
+    };
+
+    let help_keys = if app.accepts_text() {
+        app.keys.command_keys(Action::Help)
+    } else {
+        app.keys.keys(Action::Help)
+    };
+    if !help_keys.is_empty() {
+        entries.push((help_keys, Action::Help.description()));
+    }
+    entries
+}
+
+fn render_key_overlay(f: &mut Frame, app: &TuiApp) {
+    let entries = key_help(app);
+    let width = entries
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = entries
+        .into_iter()
+        .map(|(keys, description)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>width$}  ", keys, width = width),
+                    Style::default()
+                        .fg(theme::current().warning)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(description),
+            ])
+        })
+        .collect();
+
+    let area = centered_rect(70, 80, f.size());
+    f.render_widget(Clear, area);
+    let overlay = Paragraph::new(lines).block(bordered().title("Keys (press any key to close)"));
+    f.render_widget(overlay, area);
 }
 
 fn render_provider_list(f: &mut Frame, app: &mut TuiApp, area: Rect) {
+    let compact = app.compact;
+    let bar = if compact { 1 } else { 3 };
+    let area = if app.recent.is_empty() {
+        area
+    } else {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(bar), Constraint::Min(3)])
+            .split(area);
+        render_recent(f, app, chunks[0]);
+        chunks[1]
+    };
+
+    let searching = matches!(app.input_mode, InputMode::Search);
+    let area = if searching || !app.search.is_empty() {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(bar), Constraint::Min(3)])
+            .split(area);
+        let search = Paragraph::new(format!("/{}", app.search)).block(panel(compact, "Search"));
+        f.render_widget(search, chunks[0]);
+        if searching {
+            let border = u16::from(!compact);
+            f.set_cursor(
+                chunks[0].x + app.search.chars().count() as u16 + 1 + border,
+                chunks[0].y + border,
+            );
+        }
+        chunks[1]
+    } else {
+        area
+    };
+    let query = app.search.clone();
+
+    let area = if area.width >= DETAIL_MIN_WIDTH {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(area);
+        render_detail(f, app, chunks[1]);
+        chunks[0]
+    } else {
+        area
+    };
+
     let items: Vec<ListItem> = app
-        .config
-        .providers
-        .values()
+        .view
+        .iter()
+        .filter_map(|name| app.config.providers.get(name))
         .map(|provider| {
+            let provider = &app
+                .config
+                .resolve_provider(&provider.name)
+                .unwrap_or_else(|_| provider.clone());
             let is_current = app.config.current_provider.as_ref() == Some(&provider.name);
-            let marker = if is_current { "● " } else { "○ " };
-
-            let mut lines = vec![
-                Line::from(vec![
-                    Span::styled(
-                        marker,
-                        Style::default().fg(if is_current {
-                            Color::Green
-                        } else {
-                            Color::White
-                        }),
-                    ),
-                    Span::styled(
-                        &provider.name,
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                ]),
-                Line::from(vec![
-                    Span::raw("  URL: "),
-                    Span::styled(&provider.api_url, Style::default().fg(Color::Yellow)),
-                ]),
-            ];
-
-            let masked_token = if provider.token.len() > 8 {
-                format!("{}****", &provider.token[..8])
+            let marker = format!(
+                "{} ",
+                if is_current {
+                    theme::ACTIVE
+                } else {
+                    theme::INACTIVE
+                }
+            );
+
+            let mut header = Line::from(vec![Span::styled(
+                marker,
+                Style::default().fg(if is_current {
+                    theme::current().success
+                } else {
+                    theme::current().text
+                }),
+            )]);
+            header.spans.extend(highlighted(
+                &provider.name,
+                &query,
+                Style::default()
+                    .fg(theme::current().accent)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            if let Some(health) = app.health.get(&provider.name) {
+                header.spans.push(Span::raw("  "));
+                header.spans.push(health_span(health, false));
+            }
+
+            // One line per provider: name, health, URL and model
+            if compact {
+                header.spans.push(Span::raw("  "));
+                header.spans.extend(highlighted(
+                    &provider.api_url,
+                    &query,
+                    Style::default().fg(theme::current().warning),
+                ));
+                if let Some(ref model) = provider.model {
+                    header.spans.push(Span::raw("  "));
+                    header.spans.extend(highlighted(
+                        model,
+                        &query,
+                        Style::default().fg(theme::current().model),
+                    ));
+                }
+                return ListItem::new(header);
+            }
+
+            let mut lines = vec![header, Line::from(Span::raw("  URL: "))];
+            lines[1].spans.extend(highlighted(
+                &provider.api_url,
+                &query,
+                Style::default().fg(theme::current().warning),
+            ));
+
+            let masked_token = if !provider.has_token() {
+                "(missing)".to_string()
             } else {
-                "****".to_string()
+                mask_token(&provider.token)
             };
             lines.push(Line::from(vec![
                 Span::raw("  Token: "),
-                Span::styled(masked_token, Style::default().fg(Color::DarkGray)),
+                Span::styled(masked_token, Style::default().fg(theme::current().muted)),
             ]));
 
             if let Some(ref model) = provider.model {
-                lines.push(Line::from(vec![
-                    Span::raw("  Model: "),
-                    Span::styled(model, Style::default().fg(Color::Magenta)),
-                ]));
+                let mut spans = vec![Span::raw("  Model: ")];
+                spans.extend(highlighted(
+                    model,
+                    &query,
+                    Style::default().fg(theme::current().model),
+                ));
+                lines.push(Line::from(spans));
+            }
+
+            if !provider.tags.is_empty() || provider.group.is_some() {
+                let mut spans = Vec::new();
+                if !provider.tags.is_empty() {
+                    spans.push(Span::raw("  Tags: "));
+                    spans.extend(highlighted(
+                        &provider.tags.join(", "),
+                        &query,
+                        Style::default().fg(theme::current().info),
+                    ));
+                }
+                if let Some(ref group) = provider.group {
+                    spans.push(Span::raw("  Group: "));
+                    spans.push(Span::styled(
+                        group.clone(),
+                        Style::default().fg(theme::current().info),
+                    ));
+                }
+                lines.push(Line::from(spans));
             }
 
             if is_current {
                 lines.push(Line::from(Span::styled(
                     "  (currently active)",
                     Style::default()
-                        .fg(Color::Green)
+                        .fg(theme::current().success)
                         .add_modifier(Modifier::ITALIC),
                 )));
             }
@@ -446,26 +1768,300 @@ fn render_provider_list(f: &mut Frame, app: &mut TuiApp, area: Rect) {
         })
         .collect();
 
+    let heights: Vec<u16> = items.iter().map(|item| item.height() as u16).collect();
+
+    let title = match &app.tag_filter {
+        Some(tag) => format!("Service Providers [tag: {}]", tag),
+        None => "Service Providers".to_string(),
+    };
+
     let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Service Providers"),
-        )
-        .highlight_style(
-            Style::default()
-                .bg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
-        )
+        .block(bordered().title(title))
+        .highlight_style(theme::current().selection)
         .highlight_symbol(">> ");
 
     f.render_stateful_widget(list, area, &mut app.list_state);
+
+    let rows = area.inner(&Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+    app.list_area = rows;
+    app.item_heights = heights;
+    if app.item_heights.iter().sum::<u16>() > rows.height {
+        let mut scrollbar =
+            ScrollbarState::new(app.view.len()).position(app.list_state.selected().unwrap_or(0));
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .symbols(theme::current().scrollbar_set())
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(&Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            &mut scrollbar,
+        );
+    }
 }
 
-fn render_add_provider_form(f: &mut Frame, state: &AddProviderState, area: Rect) {
-    let block = Block::default()
+/// Block framed with the theme's border lines
+fn bordered<'a>() -> Block<'a> {
+    Block::default()
         .borders(Borders::ALL)
-        .title("Add New Provider");
+        .border_set(theme::current().border_set())
+}
+
+/// Bordered panel, or a bare area in compact mode where rows are scarce
+fn panel(compact: bool, title: &str) -> Block<'_> {
+    if compact {
+        Block::default()
+    } else {
+        bordered().title(title)
+    }
+}
+
+/// Everything about the selected provider, with inherited fields filled in
+fn render_detail(f: &mut Frame, app: &TuiApp, area: Rect) {
+    let block = bordered().title("Details");
+    let Some(selected) = app.get_selected_provider() else {
+        f.render_widget(Paragraph::new("No provider selected").block(block), area);
+        return;
+    };
+    let provider = app
+        .config
+        .resolve_provider(&selected.name)
+        .unwrap_or_else(|_| selected.clone());
+
+    let label =
+        |text: &'static str| Span::styled(text, Style::default().fg(theme::current().label));
+    let value = |text: String, color: Color| Span::styled(text, Style::default().fg(color));
+    let unset = || Span::styled("(not set)", Style::default().fg(theme::current().muted));
+
+    let is_current = app.config.current_provider.as_ref() == Some(&provider.name);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            provider.name.clone(),
+            Style::default()
+                .fg(theme::current().accent)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(vec![
+            label("Status:  "),
+            if is_current {
+                value("active".to_string(), theme::current().success)
+            } else {
+                value("inactive".to_string(), theme::current().muted)
+            },
+        ]),
+        Line::from(vec![
+            label("URL:     "),
+            value(provider.api_url.clone(), theme::current().warning),
+        ]),
+        Line::from(vec![
+            label("Auth:    "),
+            value(
+                provider.auth_style().env_var().to_string(),
+                theme::current().text,
+            ),
+        ]),
+    ];
+
+    if let Some(health) = app.health.get(&provider.name) {
+        lines.push(Line::from(vec![
+            label("Health:  "),
+            health_span(health, true),
+        ]));
+    }
+
+    let token = if !provider.has_token() {
+        value("(missing)".to_string(), theme::current().error)
+    } else if app.is_revealed(&provider.name) {
+        value(provider.token.clone(), theme::current().highlight)
+    } else {
+        value(mask_token(&provider.token), theme::current().muted)
+    };
+    lines.push(Line::from(vec![label("Token:   "), token]));
+
+    let models = [
+        ("Model:   ", &provider.model),
+        ("Opus:    ", &provider.opus_model),
+        ("Sonnet:  ", &provider.sonnet_model),
+        ("Haiku:   ", &provider.haiku_model),
+    ];
+    for (name, model) in models {
+        let model = match model {
+            Some(model) => value(model.clone(), theme::current().model),
+            None => unset(),
+        };
+        lines.push(Line::from(vec![label(name), model]));
+    }
+
+    let optional = [
+        ("Extends: ", selected.extends.clone()),
+        ("Group:   ", provider.group.clone()),
+        (
+            "Tags:    ",
+            (!provider.tags.is_empty()).then(|| provider.tags.join(", ")),
+        ),
+    ];
+    for (name, text) in optional {
+        if let Some(text) = text {
+            lines.push(Line::from(vec![
+                label(name),
+                value(text, theme::current().info),
+            ]));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, area);
+}
+
+/// Status icon and latency of a probe; `detailed` adds the failure reason
+fn health_span(health: &Health, detailed: bool) -> Span<'static> {
+    let (text, color) = match health {
+        Health::Checking => (
+            format!("{} checking", theme::CHECKING),
+            theme::current().muted,
+        ),
+        Health::Up { latency } => (
+            format!("{} {} ms", theme::ACTIVE, latency.as_millis()),
+            theme::current().success,
+        ),
+        Health::Rejected { status, latency } if detailed => (
+            format!(
+                "{} HTTP {} after {} ms",
+                theme::REJECTED,
+                status,
+                latency.as_millis()
+            ),
+            theme::current().warning,
+        ),
+        Health::Rejected { status, .. } => (
+            format!("{} HTTP {}", theme::REJECTED, status),
+            theme::current().warning,
+        ),
+        Health::Down(reason) if detailed => (
+            format!("{} unreachable: {}", theme::DOWN, reason),
+            theme::current().error,
+        ),
+        Health::Down(_) => (format!("{} down", theme::DOWN), theme::current().error),
+    };
+    Span::styled(text, Style::default().fg(color))
+}
+
+/// How well `query` matches any of the searchable fields, `None` when none match
+fn search_score(provider: &Provider, query: &str) -> Option<i64> {
+    let tags = provider.tags.join(", ");
+    [
+        Some(provider.name.as_str()),
+        Some(provider.api_url.as_str()),
+        provider.model.as_deref(),
+        Some(tags.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|field| fuzzy::fuzzy_match(query, field).map(|m| m.score))
+    .max()
+}
+
+/// Spans for `text` with the characters matched by `query` highlighted
+fn highlighted(text: &str, query: &str, style: Style) -> Vec<Span<'static>> {
+    let positions = if query.is_empty() {
+        Vec::new()
+    } else {
+        fuzzy::fuzzy_match(query, text)
+            .map(|m| m.positions)
+            .unwrap_or_default()
+    };
+    if positions.is_empty() {
+        return vec![Span::styled(text.to_string(), style)];
+    }
+
+    let highlight = style
+        .fg(theme::current().highlight)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    text.chars()
+        .enumerate()
+        .map(|(index, c)| {
+            let style = if positions.contains(&index) {
+                highlight
+            } else {
+                style
+            };
+            Span::styled(c.to_string(), style)
+        })
+        .collect()
+}
+
+fn render_recent(f: &mut Frame, app: &TuiApp, area: Rect) {
+    let mut spans = Vec::new();
+    for (index, name) in app.recent.iter().enumerate() {
+        if index > 0 {
+            spans.push(Span::raw("   "));
+        }
+        spans.push(Span::styled(
+            format!("{} ", index + 1),
+            Style::default().fg(theme::current().warning),
+        ));
+        spans.push(Span::styled(
+            name.clone(),
+            Style::default().fg(theme::current().accent),
+        ));
+    }
+
+    let recent = Paragraph::new(Line::from(spans)).block(panel(app.compact, "Recent"));
+    f.render_widget(recent, area);
+}
+
+fn render_preset_picker(f: &mut Frame, selected: usize, area: Rect) {
+    let mut items = vec![ListItem::new(Line::from(Span::styled(
+        "Custom provider",
+        Style::default()
+            .fg(theme::current().accent)
+            .add_modifier(Modifier::BOLD),
+    )))];
+    items.extend(PRESETS.iter().map(|preset| {
+        ListItem::new(vec![
+            Line::from(vec![
+                Span::styled(
+                    preset.id,
+                    Style::default()
+                        .fg(theme::current().accent)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::raw(preset.title),
+            ]),
+            Line::from(vec![
+                Span::raw("  URL: "),
+                Span::styled(
+                    preset.api_url,
+                    Style::default().fg(theme::current().warning),
+                ),
+            ]),
+        ])
+    }));
+
+    let list = List::new(items)
+        .block(bordered().title("Add New Provider: Choose a Preset"))
+        .highlight_style(theme::current().selection)
+        .highlight_symbol(">> ");
+
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+fn render_add_provider_form(f: &mut Frame, state: &AddProviderState, config: &Config, area: Rect) {
+    let title = match state.preset {
+        Some(preset) => format!("Add New Provider ({})", preset.title),
+        None => "Add New Provider".to_string(),
+    };
+    let block = bordered().title(title);
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -482,50 +2078,129 @@ fn render_add_provider_form(f: &mut Frame, state: &AddProviderState, area: Rect)
         ])
         .split(inner);
 
-    let fields = [
-        ("Name", &state.name, 0),
-        ("API URL", &state.url, 1),
-        ("Token", &state.token, 2),
-        ("Model (optional)", &state.model, 3),
-    ];
+    let labels = ["Name", "API URL", "Token", "Model (optional)"];
 
-    for (i, (label, value, field_idx)) in fields.iter().enumerate() {
-        let is_active = state.current_field == *field_idx;
+    for (i, label) in labels.into_iter().enumerate() {
+        let input = state.field(i);
+        let error = state.visible_error(i, config);
+        let is_active = state.current_field == i;
         let style = if is_active {
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme::current().warning)
                 .add_modifier(Modifier::BOLD)
         } else {
             Style::default()
         };
 
-        let border_style = if is_active {
-            Style::default().fg(Color::Cyan)
+        let border_style = if error.is_some() {
+            Style::default().fg(theme::current().error)
+        } else if is_active {
+            Style::default().fg(theme::current().accent)
         } else {
             Style::default()
         };
 
-        let input = Paragraph::new(value.as_str()).style(style).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(*label)
-                .border_style(border_style),
-        );
-        f.render_widget(input, chunks[i]);
+        // Scroll long values such as tokens so the cursor stays in view
+        let width = chunks[i].width.saturating_sub(2).max(1) as usize;
+        let offset = input.cursor().saturating_sub(width - 1);
+        let text: String = if i == 2 {
+            "*".repeat(
+                input
+                    .as_str()
+                    .chars()
+                    .count()
+                    .saturating_sub(offset)
+                    .min(width),
+            )
+        } else {
+            input.as_str().chars().skip(offset).take(width).collect()
+        };
+
+        let mut block = bordered().title(label).border_style(border_style);
+        if let Some(error) = error {
+            block = block.title_bottom(Line::styled(
+                format!(" {} ", error),
+                Style::default().fg(theme::current().error),
+            ));
+        }
+        f.render_widget(Paragraph::new(text).style(style).block(block), chunks[i]);
 
         // Show cursor
-        if is_active {
-            f.set_cursor(chunks[i].x + value.len() as u16 + 1, chunks[i].y + 1);
+        if is_active && state.model_picker.is_none() {
+            f.set_cursor(
+                chunks[i].x + (input.cursor() - offset) as u16 + 1,
+                chunks[i].y + 1,
+            );
         }
     }
+
+    if let Some((ref models, selected)) = state.model_picker {
+        render_model_picker(f, models, selected, area);
+    }
+}
+
+fn render_model_picker(f: &mut Frame, models: &[String], selected: usize, area: Rect) {
+    let items: Vec<ListItem> = models
+        .iter()
+        .map(|model| ListItem::new(model.as_str()))
+        .collect();
+
+    let list = List::new(items)
+        .block(bordered().title(format!("Pick a Model ({})", models.len())))
+        .highlight_style(theme::current().selection)
+        .highlight_symbol(">> ");
+
+    let popup_area = centered_rect(60, 70, area);
+    let mut list_state = ListState::default();
+    list_state.select(Some(selected));
+    f.render_widget(Clear, popup_area);
+    f.render_stateful_widget(list, popup_area, &mut list_state);
+}
+
+fn render_models_loading(f: &mut Frame, provider: &str, area: Rect) {
+    let popup = centered_rect(60, 100, area);
+    let popup = Rect {
+        y: area.y + area.height.saturating_sub(3) / 2,
+        height: 3.min(area.height),
+        ..popup
+    };
+    let text = format!("{} Asking '{}' for its models", theme::CHECKING, provider);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(text)
+            .style(Style::default().fg(theme::current().muted))
+            .alignment(Alignment::Center)
+            .block(bordered().title("Loading Models")),
+        popup,
+    );
+}
+
+fn render_model_input(f: &mut Frame, provider: &str, input: &LineInput, area: Rect) {
+    let popup = centered_rect(60, 100, area);
+    let popup = Rect {
+        y: area.y + area.height.saturating_sub(3) / 2,
+        height: 3.min(area.height),
+        ..popup
+    };
+
+    // Scroll long names so the cursor stays in view, as in the add form
+    let width = popup.width.saturating_sub(2).max(1) as usize;
+    let offset = input.cursor().saturating_sub(width - 1);
+    let text: String = input.as_str().chars().skip(offset).take(width).collect();
+
+    let block = bordered()
+        .title(format!("Model for '{}'", provider))
+        .border_style(Style::default().fg(theme::current().accent));
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(text).block(block), popup);
+    f.set_cursor(popup.x + (input.cursor() - offset) as u16 + 1, popup.y + 1);
 }
 
 fn render_delete_confirmation(f: &mut Frame, app: &TuiApp, area: Rect) {
     if let Some(provider) = app.get_selected_provider() {
-        let block = Block::default()
-            .borders(Borders::ALL)
+        let block = bordered()
             .title("Confirm Delete")
-            .style(Style::default().bg(Color::Red).fg(Color::White));
+            .style(theme::current().alert);
 
         let text = format!(
             "Are you sure you want to delete provider '{}'?\n\nPress 'y' to confirm, 'n' to cancel",
@@ -561,3 +2236,363 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Group;
+    use crate::error::CceError;
+    use crate::test_support::TestHome;
+    use ratatui::backend::TestBackend;
+    use std::ops::{Deref, DerefMut};
+
+    /// An app with its own home directory, since actions save the config
+    struct TestApp {
+        app: TuiApp,
+        home: TestHome,
+    }
+
+    impl Deref for TestApp {
+        type Target = TuiApp;
+
+        fn deref(&self) -> &TuiApp {
+            &self.app
+        }
+    }
+
+    impl DerefMut for TestApp {
+        fn deref_mut(&mut self) -> &mut TuiApp {
+            &mut self.app
+        }
+    }
+
+    fn app_with(names: &[&str]) -> TestApp {
+        let home = TestHome::new();
+        let mut config = Config::default();
+        for name in names {
+            config.add_provider(
+                name.to_string(),
+                format!("https://{}.example.com", name),
+                format!("sk-{}-token", name),
+                None,
+            );
+        }
+        TestApp {
+            app: TuiApp::new(config, KeyMap::default()),
+            home,
+        }
+    }
+
+    fn press(app: &mut TuiApp, key: KeyCode) {
+        app.handle_input(key, KeyModifiers::NONE).unwrap();
+    }
+
+    fn type_text(app: &mut TuiApp, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    fn click(app: &mut TuiApp, column: u16, row: u16) {
+        app.handle_mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+        .unwrap();
+    }
+
+    /// The screen rows of one frame
+    fn draw(app: &mut TuiApp, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| ui(f, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    /// Take in the model list once the background request answers
+    fn wait_for_models(app: &mut TuiApp) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while matches!(app.input_mode, InputMode::LoadingModels { .. }) {
+            assert!(Instant::now() < deadline, "the model list never arrived");
+            thread::sleep(Duration::from_millis(10));
+            app.collect_models();
+        }
+    }
+
+    /// The row carrying the highlight symbol, as drawn
+    fn highlighted_row(app: &mut TuiApp) -> String {
+        draw(app, 160, 60)
+            .into_iter()
+            .find(|row| row.contains(">> "))
+            .expect("no highlighted row")
+    }
+
+    #[test]
+    fn lists_providers_by_name() {
+        let mut app = app_with(&["kimi", "deepseek", "ollama", "anthropic"]);
+        assert_eq!(app.view, ["anthropic", "deepseek", "kimi", "ollama"]);
+        assert!(highlighted_row(&mut app).contains("anthropic"));
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.get_selected_provider().unwrap().name, "kimi");
+        assert!(highlighted_row(&mut app).contains("kimi"));
+    }
+
+    #[test]
+    fn delete_removes_the_highlighted_provider() {
+        let mut app = app_with(&["anthropic", "deepseek", "kimi"]);
+        press(&mut app, KeyCode::Down);
+        assert!(highlighted_row(&mut app).contains("deepseek"));
+        app.health.insert("deepseek".to_string(), Health::Checking);
+
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('y'));
+        assert!(!app.config.providers.contains_key("deepseek"));
+        assert!(!app.health.contains_key("deepseek"));
+        assert_eq!(app.view, ["anthropic", "kimi"]);
+        // The next provider moves up into the deleted row
+        assert!(highlighted_row(&mut app).contains("kimi"));
+    }
+
+    #[test]
+    fn probing_again_keeps_the_last_results_until_new_ones_arrive() {
+        let mut app = app_with(&["anthropic", "kimi"]);
+        let latency = Duration::from_millis(120);
+        app.health
+            .insert("kimi".to_string(), Health::Up { latency });
+        // Left over from a provider deleted outside the TUI
+        app.health
+            .insert("gone".to_string(), Health::Up { latency });
+
+        press(&mut app, KeyCode::Char('t'));
+        assert!(matches!(app.health["kimi"], Health::Up { .. }));
+        assert!(matches!(app.health["anthropic"], Health::Checking));
+        assert!(!app.health.contains_key("gone"));
+    }
+
+    #[test]
+    fn short_terminal_lists_one_line_per_provider_and_takes_clicks() {
+        let mut app = app_with(&["anthropic", "deepseek", "kimi"]);
+        let rows = draw(&mut app, 90, 20);
+        assert!(app.compact);
+        let row = rows
+            .iter()
+            .position(|row| row.contains("○ kimi"))
+            .expect("kimi is not listed");
+        assert!(rows[row].contains("https://kimi.example.com"));
+
+        click(&mut app, 10, row as u16);
+        assert_eq!(app.get_selected_provider().unwrap().name, "kimi");
+        assert_eq!(app.config.current_provider, None);
+        click(&mut app, 10, row as u16);
+        assert_eq!(app.config.current_provider.as_deref(), Some("kimi"));
+
+        app.handle_mouse(MouseEvent {
+            kind: MouseEventKind::ScrollUp,
+            column: 10,
+            row: row as u16,
+            modifiers: KeyModifiers::NONE,
+        })
+        .unwrap();
+        assert!(draw(&mut app, 90, 20)
+            .iter()
+            .any(|row| row.contains(">> ○ deepseek")));
+    }
+
+    #[test]
+    fn undo_brings_back_deleted_provider() {
+        let mut app = app_with(&["anthropic", "deepseek", "kimi"]);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.config.current_provider, None);
+
+        press(&mut app, KeyCode::Char('U'));
+        assert_eq!(app.config.providers["deepseek"].token, "sk-deepseek-token");
+        assert!(app.health.contains_key("deepseek"));
+        assert_eq!(app.config.current_provider.as_deref(), Some("deepseek"));
+        assert!(highlighted_row(&mut app).contains("deepseek"));
+
+        // Then the switch to it
+        press(&mut app, KeyCode::Char('U'));
+        assert_eq!(app.config.current_provider, None);
+    }
+
+    #[test]
+    fn undo_makes_restored_provider_its_groups_default_again() {
+        let mut app = app_with(&["anthropic", "deepseek", "kimi"]);
+        for name in ["deepseek", "kimi"] {
+            app.config.providers.get_mut(name).unwrap().group = Some("cn".to_string());
+        }
+        app.config.groups.insert(
+            "cn".to_string(),
+            Group {
+                default: Some("kimi".to_string()),
+            },
+        );
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('y'));
+        assert!(!app.config.providers.contains_key("kimi"));
+        assert_eq!(app.config.groups["cn"].default, None);
+
+        press(&mut app, KeyCode::Char('U'));
+        assert!(app.config.providers.contains_key("kimi"));
+        assert_eq!(app.config.groups["cn"].default.as_deref(), Some("kimi"));
+    }
+
+    #[test]
+    fn added_provider_is_highlighted_in_sorted_position() {
+        let mut app = app_with(&["anthropic", "ollama"]);
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Enter);
+        type_text(&mut app, "glm");
+        press(&mut app, KeyCode::Tab);
+        type_text(&mut app, "https://glm.example.com");
+        press(&mut app, KeyCode::Tab);
+        type_text(&mut app, "sk-glm-token");
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Enter);
+
+        assert_eq!(app.view, ["anthropic", "glm", "ollama"]);
+        assert!(app.health.contains_key("glm"));
+        assert_eq!(app.list_state.selected(), Some(1));
+        assert!(highlighted_row(&mut app).contains("glm"));
+    }
+
+    #[test]
+    fn keys_handled_before_the_keymap_cannot_be_bound() {
+        for keys in [
+            "quit = \"ctrl-c\"",
+            "use = [\"enter\", \"1\"]",
+            "test = \"alt-9\"",
+        ] {
+            let overrides = toml::from_str(keys).unwrap();
+            let err = KeyMap::from_config(&overrides).err().expect(keys);
+            assert!(err.to_string().contains("is reserved"), "{}: {}", keys, err);
+            assert!(matches!(
+                err.downcast_ref::<CceError>(),
+                Some(CceError::ConfigInvalid(_))
+            ));
+        }
+        let overrides = toml::from_str("test = \"0\"").unwrap();
+        assert!(KeyMap::from_config(&overrides).is_ok());
+    }
+
+    #[test]
+    fn configured_keys_move_the_selection_and_show_in_the_help_overlay() {
+        let mut app = app_with(&["anthropic", "deepseek", "kimi"]);
+        let overrides = toml::from_str(
+            r#"
+            down = ["down", "ctrl-n"]
+            up = "ctrl-p"
+            "#,
+        )
+        .unwrap();
+        app.keys = KeyMap::from_config(&overrides).unwrap();
+
+        app.handle_input(KeyCode::Char('n'), KeyModifiers::CONTROL)
+            .unwrap();
+        assert_eq!(app.get_selected_provider().unwrap().name, "deepseek");
+        // k is no longer bound, so it leaves the selection alone
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.get_selected_provider().unwrap().name, "deepseek");
+
+        press(&mut app, KeyCode::Char('?'));
+        assert!(app.show_help);
+        let rows = draw(&mut app, 160, 60);
+        assert!(rows.iter().any(|row| row.contains("↓/Ctrl+N  Move down")));
+        assert!(rows.iter().any(|row| row.contains("Ctrl+P  Move up")));
+
+        // Any key closes the overlay without acting on it
+        press(&mut app, KeyCode::Char('d'));
+        assert!(!app.show_help);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+    }
+
+    #[test]
+    fn conflicting_key_bindings_are_rejected() {
+        let overrides = toml::from_str(r#"quit = ["q", "d"]"#).unwrap();
+        let err = KeyMap::from_config(&overrides).err().unwrap();
+        assert!(err.to_string().contains("bound to both"));
+    }
+
+    #[test]
+    fn hands_the_final_provider_to_the_launching_shell() {
+        let mut app = app_with(&["anthropic", "deepseek"]);
+        let handoff = app.home.path().join("handoff");
+        app.handoff = Some(handoff.clone());
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        app.hand_off().unwrap();
+        let commands = std::fs::read_to_string(&handoff).unwrap();
+        assert!(commands.contains("export ANTHROPIC_BASE_URL='https://deepseek.example.com'"));
+        assert!(commands.contains("export CCE_PROVIDER='deepseek'"));
+
+        press(&mut app, KeyCode::Char('c'));
+        app.hand_off().unwrap();
+        let commands = std::fs::read_to_string(&handoff).unwrap();
+        assert!(commands.contains("unset CCE_PROVIDER"));
+        assert!(!commands.contains("export"));
+    }
+
+    #[test]
+    fn selection_follows_provider_when_the_filter_changes() {
+        let mut app = app_with(&["anthropic", "deepseek", "kimi"]);
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "kimi");
+        // Characters typed with Ctrl or Alt held are not part of the search
+        for modifiers in [KeyModifiers::ALT, KeyModifiers::CONTROL] {
+            app.handle_input(KeyCode::Char('x'), modifiers).unwrap();
+        }
+        assert_eq!(app.search, "kimi");
+        assert_eq!(app.view, ["kimi"]);
+        assert!(highlighted_row(&mut app).contains("kimi"));
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.config.current_provider.as_deref(), Some("kimi"));
+        // Clearing the search lists everything again without losing the provider acted on
+        assert_eq!(app.view, ["anthropic", "deepseek", "kimi"]);
+        assert!(highlighted_row(&mut app).contains("kimi"));
+    }
+
+    #[test]
+    fn models_are_typed_in_when_the_gateway_cannot_list_them() {
+        let mut app = app_with(&["offline"]);
+        // Nothing listens on the discard port, so the model list cannot be loaded
+        let provider = app.config.providers.get_mut("offline").unwrap();
+        provider.api_url = "http://127.0.0.1:9".to_string();
+        provider.opus_model = Some("preset-opus".to_string());
+
+        press(&mut app, KeyCode::Char('m'));
+        assert!(draw(&mut app, 120, 40)
+            .iter()
+            .any(|row| row.contains("Asking 'offline' for its models")));
+        wait_for_models(&mut app);
+        assert!(matches!(app.input_mode, InputMode::ModelInput { .. }));
+        assert!(draw(&mut app, 120, 40)
+            .iter()
+            .any(|row| row.contains("Model for 'offline'")));
+
+        // Letters bound to actions are text here
+        type_text(&mut app, "kimi-k2");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.input_mode, InputMode::Normal));
+        let provider = &app.config.providers["offline"];
+        assert_eq!(provider.model.as_deref(), Some("kimi-k2"));
+        assert_eq!(provider.opus_model, None);
+    }
+}